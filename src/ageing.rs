@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::jam::{self, JamEffect, JamIngredient};
+use crate::utils::load_data;
+
+pub struct AgeingPlugin;
+
+impl Plugin for AgeingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, setup.system())
+            .insert_resource(GameClock::default())
+            .add_system(tick_clock.system())
+            .add_system(fast_forward_key.system());
+    }
+}
+
+/// game hours that pass each real second
+const HOURS_PER_SECOND: f64 = 0.1;
+/// how much faster the clock runs while fast forwarding
+const FAST_FORWARD: f64 = 8.0;
+
+/// the time in the game world, jam ages by this rather than the real clock so
+/// the same hours always give the same jam
+pub struct GameClock {
+    hours: f64,
+    speed: f64,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            hours: 0.0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl GameClock {
+    pub fn hours(&self) -> f64 {
+        self.hours
+    }
+
+    pub fn advance(&mut self, hours: f64) {
+        self.hours += hours;
+    }
+}
+
+fn setup(commands: &mut Commands) {
+    commands.insert_resource(load_data::<Ageing>("ageing.ron"));
+}
+
+fn tick_clock(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    let hours = time.delta_seconds_f64() * HOURS_PER_SECOND * clock.speed;
+    clock.advance(hours);
+}
+
+/// F4 toggles fast forward
+fn fast_forward_key(keys: Res<Input<KeyCode>>, mut clock: ResMut<GameClock>) {
+    if keys.just_pressed(KeyCode::F4) {
+        clock.speed = if clock.speed > 1.0 { 1.0 } else { FAST_FORWARD };
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub enum Condition {
+    /// every jar
+    Any,
+    /// the jar was made with the ingredient
+    Has(JamIngredient),
+    /// the jar had the effect when it was bottled
+    HasEffect(JamEffect),
+}
+
+#[derive(Deserialize, Debug)]
+pub enum Change {
+    /// takes from the strength of an effect
+    Fade(JamEffect, u32),
+    /// adds to the strength of an effect
+    Ferment(JamEffect, u32),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AgeingRule {
+    /// how many hours old the jar has to be
+    after: f64,
+    when: Condition,
+    then: Change,
+}
+
+impl AgeingRule {
+    fn applies(&self, ingredients: &[JamIngredient], bottled: &HashSet<JamEffect>) -> bool {
+        match &self.when {
+            Condition::Any => true,
+            Condition::Has(ingredient) => ingredients.contains(ingredient),
+            Condition::HasEffect(effect) => bottled.contains(effect),
+        }
+    }
+}
+
+/// how jam changes the longer it sits on the shelf
+#[derive(Deserialize, Debug)]
+pub struct Ageing {
+    /// hours until a jar goes bad
+    spoils_after: f64,
+    rules: Vec<AgeingRule>,
+}
+
+/// what a jar is like at some age
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aged {
+    pub effects: HashSet<JamEffect>,
+    /// gone bad, it'll make whoever eats it sick
+    pub spoiled: bool,
+}
+
+impl Ageing {
+    /// the jam made with `ingredients` to `strengths` after `hours` on the
+    /// shelf, this only depends on the age so it's the same however the time
+    /// passed
+    pub fn age(
+        &self,
+        ingredients: &[JamIngredient],
+        strengths: &HashMap<JamEffect, u32>,
+        hours: f64,
+    ) -> Aged {
+        let bottled = jam::active_effects(strengths);
+        let mut strengths = strengths.clone();
+
+        for rule in &self.rules {
+            if hours < rule.after || !rule.applies(ingredients, &bottled) {
+                continue;
+            }
+
+            match rule.then {
+                Change::Fade(effect, amount) => {
+                    if let Some(strength) = strengths.get_mut(&effect) {
+                        *strength = strength.saturating_sub(amount);
+                    }
+                }
+                Change::Ferment(effect, amount) => {
+                    *strengths.entry(effect).or_insert(0) += amount;
+                }
+            }
+        }
+
+        Aged {
+            effects: jam::active_effects(&strengths),
+            spoiled: hours >= self.spoils_after,
+        }
+    }
+}
+
+/// a jar's age for its label
+pub fn describe_age(hours: f64) -> String {
+    let hours = hours.floor() as u64;
+
+    match (hours / 24, hours % 24) {
+        (0, 1) => "1 hour old".to_string(),
+        (0, hours) => format!("{} hours old", hours),
+        (1, _) => "1 day old".to_string(),
+        (days, _) => format!("{} days old", days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ageing() -> Ageing {
+        Ageing {
+            spoils_after: 96.0,
+            rules: vec![
+                AgeingRule {
+                    after: 12.0,
+                    when: Condition::HasEffect(JamEffect::Speed),
+                    then: Change::Fade(JamEffect::Speed, 1),
+                },
+                AgeingRule {
+                    after: 24.0,
+                    when: Condition::Has(JamIngredient::Absinth),
+                    then: Change::Ferment(JamEffect::Flammable, 2),
+                },
+                AgeingRule {
+                    after: 36.0,
+                    when: Condition::Any,
+                    then: Change::Fade(JamEffect::GreaterHeal, 1),
+                },
+            ],
+        }
+    }
+
+    fn strengths(effects: &[(JamEffect, u32)]) -> HashMap<JamEffect, u32> {
+        effects.iter().cloned().collect()
+    }
+
+    fn effects(effects: &[JamEffect]) -> HashSet<JamEffect> {
+        effects.iter().cloned().collect()
+    }
+
+    #[test]
+    fn fades() {
+        let ageing = ageing();
+        let bottled = strengths(&[(JamEffect::Speed, 2), (JamEffect::GreaterHeal, 3)]);
+
+        let young = ageing.age(&[], &bottled, 11.9);
+        assert_eq!(
+            young.effects,
+            effects(&[JamEffect::Speed, JamEffect::GreaterHeal])
+        );
+
+        let faded = ageing.age(&[], &bottled, 12.0);
+        assert_eq!(faded.effects, effects(&[JamEffect::GreaterHeal]));
+
+        // still strong enough after losing one
+        let old = ageing.age(&[], &bottled, 40.0);
+        assert_eq!(old.effects, effects(&[JamEffect::GreaterHeal]));
+    }
+
+    #[test]
+    fn ferments() {
+        let ageing = ageing();
+        let ingredients = [JamIngredient::Absinth];
+
+        let young = ageing.age(&ingredients, &HashMap::new(), 23.0);
+        assert!(young.effects.is_empty());
+
+        let fermented = ageing.age(&ingredients, &HashMap::new(), 24.0);
+        assert_eq!(fermented.effects, effects(&[JamEffect::Flammable]));
+
+        // only jars made with it ferment
+        let other = ageing.age(&[JamIngredient::Lemons], &HashMap::new(), 24.0);
+        assert!(other.effects.is_empty());
+    }
+
+    #[test]
+    fn spoils() {
+        let ageing = ageing();
+
+        assert!(!ageing.age(&[], &HashMap::new(), 95.9).spoiled);
+        assert!(ageing.age(&[], &HashMap::new(), 96.0).spoiled);
+        assert!(ageing.age(&[], &HashMap::new(), 500.0).spoiled);
+    }
+
+    #[test]
+    fn only_the_age_matters() {
+        let ageing = ageing();
+        let ingredients = [JamIngredient::Absinth];
+        let bottled = strengths(&[(JamEffect::Speed, 2), (JamEffect::GreaterHeal, 2)]);
+
+        // ageing it bit by bit ends up with the same jar as jumping straight
+        // there
+        let mut last = None;
+        for hour in 0..=60 {
+            last = Some(ageing.age(&ingredients, &bottled, hour as f64));
+        }
+
+        let jumped = ageing.age(&ingredients, &bottled, 60.0);
+        assert_eq!(last, Some(jumped.clone()));
+        assert_eq!(jumped, ageing.age(&ingredients, &bottled, 60.0));
+        assert_eq!(jumped.effects, effects(&[JamEffect::Flammable]));
+    }
+}
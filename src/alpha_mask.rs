@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::HandleId,
+    prelude::*,
+    render::texture::{Texture, TextureFormat},
+};
+
+pub struct AlphaMaskPlugin;
+
+impl Plugin for AlphaMaskPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(AlphaMasks::default())
+            .add_system_to_stage(CoreStage::PreUpdate, update_masks.system());
+    }
+}
+
+/// pixels less opaque than this can be clicked through
+const ALPHA_CUTOFF: u8 = 25;
+
+/// which pixels of a texture can be clicked on
+struct AlphaMask {
+    width: u32,
+    height: u32,
+    solid: Vec<bool>,
+}
+
+impl AlphaMask {
+    /// `None` for formats we can't read the alpha of
+    fn new(texture: &Texture) -> Option<Self> {
+        match texture.format {
+            TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb => {}
+            _ => return None,
+        }
+
+        Some(AlphaMask {
+            width: texture.size.width,
+            height: texture.size.height,
+            solid: texture
+                .data
+                .chunks_exact(4)
+                .map(|pixel| pixel[3] >= ALPHA_CUTOFF)
+                .collect(),
+        })
+    }
+}
+
+/// the alpha masks of every loaded texture, made as they load so picking only
+/// has to look a pixel up
+#[derive(Default)]
+pub struct AlphaMasks {
+    masks: HashMap<HandleId, AlphaMask>,
+}
+
+impl AlphaMasks {
+    /// whether the texture is solid at `uv`, from the top left of `frame` (the
+    /// part of the texture that's drawn, in pixels, the whole texture if
+    /// `None`). textures that aren't loaded or can't be read count as solid
+    pub fn solid(&self, texture: &Handle<Texture>, frame: Option<(Vec2, Vec2)>, uv: Vec2) -> bool {
+        let mask = match self.masks.get(&texture.id) {
+            Some(mask) => mask,
+            None => return true,
+        };
+
+        let (min, size) = frame.unwrap_or((
+            Vec2::zero(),
+            Vec2::new(mask.width as f32, mask.height as f32),
+        ));
+        let pixel = min + uv * size;
+
+        if pixel.x < 0.0 || pixel.y < 0.0 {
+            return false;
+        }
+
+        let (x, y) = (pixel.x as u32, pixel.y as u32);
+        if x >= mask.width || y >= mask.height {
+            return false;
+        }
+
+        mask.solid[(y * mask.width + x) as usize]
+    }
+}
+
+fn update_masks(
+    textures: Res<Assets<Texture>>,
+    mut masks: ResMut<AlphaMasks>,
+    mut event_reader: EventReader<AssetEvent<Texture>>,
+) {
+    for event in event_reader.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                match textures.get(handle).and_then(AlphaMask::new) {
+                    Some(mask) => masks.masks.insert(handle.id, mask),
+                    None => masks.masks.remove(&handle.id),
+                };
+            }
+            AssetEvent::Removed { handle } => {
+                masks.masks.remove(&handle.id);
+            }
+        }
+    }
+}
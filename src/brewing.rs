@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::cauldron_scene::CauldronContents;
+use crate::jam::{self, JamEffect, JamIngredient};
+use crate::recipes::RecipeBook;
+use crate::stirring::StirDirection;
+
+pub struct BrewingPlugin;
+
+impl Plugin for BrewingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(simmer.system());
+    }
+}
+
+/// number of notches on the heat dial
+pub const HEAT_STEPS: u32 = 5;
+/// seconds it takes to set at a medium heat
+const SIMMER_TIME: f32 = 20.0;
+/// above this heat the jam is boiling and starts to burn
+const BOIL_HEAT: f32 = 0.7;
+/// seconds it takes to burn at full heat
+const BURN_TIME: f32 = 8.0;
+/// clockwise stirs needed for each ingredient to be mixed in
+const STIRS_PER_INGREDIENT: u32 = 1;
+/// the colour badly mixed jam goes towards
+const MUDDY: Color = Color::rgb(0.35, 0.3, 0.2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrewState {
+    Empty,
+    /// hasn't simmered long enough, the effects are weaker
+    UnderCooked,
+    Set,
+    /// boiled over, adds some nasty effects
+    Burnt,
+}
+
+impl BrewState {
+    pub fn name(self) -> &'static str {
+        match self {
+            BrewState::Empty => "Empty",
+            BrewState::UnderCooked => "Simmering",
+            BrewState::Set => "Set",
+            BrewState::Burnt => "Burnt",
+        }
+    }
+}
+
+/// the cooking state of what's in a station's cauldron
+#[derive(Debug, Clone, Default)]
+pub struct Brew {
+    /// in notches, 0 to `HEAT_STEPS`
+    pub heat: u32,
+    /// 1.0 and above is set
+    pub cooked: f32,
+    /// 1.0 and above is burnt
+    pub burnt: f32,
+    /// clockwise stirs, going anticlockwise undoes them
+    pub stirs: u32,
+    /// seconds it's been boiling while full
+    boiling_full: f32,
+}
+
+impl Brew {
+    /// heat as a fraction of the dial
+    pub fn heat_fraction(&self) -> f32 {
+        self.heat as f32 / HEAT_STEPS as f32
+    }
+
+    pub fn state(&self, contents: &CauldronContents) -> BrewState {
+        if contents.0.is_empty() {
+            BrewState::Empty
+        } else if self.burnt >= 1.0 {
+            BrewState::Burnt
+        } else if self.cooked >= 1.0 {
+            BrewState::Set
+        } else {
+            BrewState::UnderCooked
+        }
+    }
+
+    /// a new ingredient goes in cold, so it takes a while to set again
+    pub fn add_ingredient(&mut self, count_after: usize) {
+        self.cooked *= (count_after - 1) as f32 / count_after as f32;
+    }
+
+    pub fn reset(&mut self) {
+        self.cooked = 0.0;
+        self.burnt = 0.0;
+        self.stirs = 0;
+    }
+
+    pub fn stir(&mut self, direction: StirDirection) {
+        match direction {
+            StirDirection::Clockwise => self.stirs += 1,
+            StirDirection::Anticlockwise => self.stirs = self.stirs.saturating_sub(1),
+        }
+    }
+
+    pub fn stirs_needed(&self, contents: &CauldronContents) -> u32 {
+        contents.0.len() as u32 * STIRS_PER_INGREDIENT
+    }
+
+    /// how well the ingredients are combined, 1.0 is fully mixed
+    pub fn mixed(&self, contents: &CauldronContents) -> f32 {
+        match self.stirs_needed(contents) {
+            0 => 1.0,
+            needed => (self.stirs as f32 / needed as f32).min(1.0),
+        }
+    }
+
+    /// hot enough to burn, and to spill if it's full
+    pub fn boiling(&self) -> bool {
+        self.heat_fraction() > BOIL_HEAT
+    }
+
+    /// whether a cauldron that's been this full for another `delta` seconds
+    /// boils over
+    pub fn boil_over(&mut self, delta: f32, full: bool, spill_time: f32) -> bool {
+        if !full || !self.boiling() {
+            self.boiling_full = 0.0;
+            return false;
+        }
+
+        self.boiling_full += delta;
+        if self.boiling_full < spill_time {
+            return false;
+        }
+
+        self.boiling_full = 0.0;
+        true
+    }
+
+    fn tick(&mut self, delta: f32) {
+        let heat = self.heat_fraction();
+
+        // a medium heat sets the jam in `SIMMER_TIME`
+        self.cooked += delta * heat * 2.0 / SIMMER_TIME;
+
+        if self.boiling() {
+            self.burnt += delta * (heat - BOIL_HEAT) / (1.0 - BOIL_HEAT) / BURN_TIME;
+        }
+    }
+
+    /// how cooking changes the strength of the ingredients' effects
+    pub fn apply(&self, state: BrewState, strengths: &mut HashMap<JamEffect, u32>) {
+        match state {
+            BrewState::Empty | BrewState::Set => {}
+            BrewState::UnderCooked => {
+                for strength in strengths.values_mut() {
+                    *strength = strength.saturating_sub(1);
+                }
+            }
+            BrewState::Burnt => {
+                for effect in &[JamEffect::Flammable, JamEffect::Poison] {
+                    let strength = strengths.entry(*effect).or_insert(0);
+                    *strength = (*strength).max(jam::EFFECT_THRESHOLD);
+                }
+            }
+        }
+    }
+
+    /// under-stirred jam loses its weakest effect
+    fn unmix(&self, strengths: &mut HashMap<JamEffect, u32>) {
+        let weakest = strengths
+            .iter()
+            .filter(|(_, s)| **s >= jam::EFFECT_THRESHOLD)
+            .min_by_key(|(e, s)| (**s, e.name()))
+            .map(|(e, _)| *e);
+
+        if let Some(effect) = weakest {
+            strengths.insert(effect, jam::EFFECT_THRESHOLD - 1);
+        }
+    }
+
+    /// the strength of each effect if it were bottled now, after recipes and
+    /// cooking
+    pub fn strengths(
+        &self,
+        contents: &CauldronContents,
+        recipes: &RecipeBook,
+    ) -> HashMap<JamEffect, u32> {
+        let mut strengths = JamIngredient::effect_strengths(&contents.0);
+        recipes.apply(&contents.0, &mut strengths);
+        self.apply(self.state(contents), &mut strengths);
+
+        if self.mixed(contents) < 1.0 {
+            self.unmix(&mut strengths);
+        }
+
+        strengths
+    }
+
+    /// the effects the jam would have if it were bottled now
+    pub fn effects(&self, contents: &CauldronContents, recipes: &RecipeBook) -> HashSet<JamEffect> {
+        jam::active_effects(&self.strengths(contents, recipes))
+    }
+
+    /// badly mixed jam is muddy, raw jam is pale and burnt jam goes black
+    pub fn tint(&self, contents: &CauldronContents, colour: Color) -> Color {
+        let colour = blend(colour, MUDDY, 0.5 * (1.0 - self.mixed(contents)));
+
+        match self.state(contents) {
+            BrewState::Empty | BrewState::Set => colour,
+            BrewState::UnderCooked => blend(colour, Color::WHITE, 0.4 * (1.0 - self.cooked)),
+            BrewState::Burnt => blend(colour, Color::BLACK, 0.7),
+        }
+    }
+
+    /// seconds between frames of the bubbling animation, `None` when it's off
+    /// the heat
+    pub fn bubble_delay(&self) -> Option<f32> {
+        if self.heat == 0 {
+            None
+        } else {
+            Some(0.45 - 0.35 * self.heat_fraction())
+        }
+    }
+}
+
+/// moves `colour` `amount` of the way to `target`, keeping its alpha
+fn blend(colour: Color, target: Color, amount: f32) -> Color {
+    Color::rgba(
+        colour.r() + (target.r() - colour.r()) * amount,
+        colour.g() + (target.g() - colour.g()) * amount,
+        colour.b() + (target.b() - colour.b()) * amount,
+        colour.a(),
+    )
+}
+
+fn simmer(time: Res<Time>, mut q_station: Query<(&CauldronContents, &mut Brew)>) {
+    for (contents, mut brew) in q_station.iter_mut() {
+        if contents.0.is_empty() {
+            continue;
+        }
+
+        brew.tick(time.delta_seconds());
+    }
+}
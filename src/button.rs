@@ -23,6 +23,44 @@ impl Default for ButtonState {
 
 pub struct ButtonPressedEvent(pub Entity);
 
+/// a plain white button with a line of text on it, at least square
+pub fn spawn_text_button<'a, 'b>(
+    parent: &'b mut ChildBuilder<'a>,
+    materials: &mut Assets<ColorMaterial>,
+    font: Handle<Font>,
+    label: &str,
+    font_size: f32,
+) -> &'b mut ChildBuilder<'a> {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                min_size: Size::new(Val::Px(font_size * 1.6), Val::Px(font_size * 1.6)),
+                margin: Rect::all(Val::Px(4.0)),
+                padding: Rect::all(Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.add(Color::WHITE.into()),
+            ..Default::default()
+        })
+        .with(ButtonState::default())
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font,
+                        font_size,
+                        color: Color::BLACK,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        })
+}
+
 fn button_system(
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut interaction_query: Query<
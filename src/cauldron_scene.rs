@@ -1,18 +1,61 @@
+use crate::brewing::{Brew, HEAT_STEPS};
 use crate::button;
-use crate::dragging::{DropTarget, DroppedOntoEvent};
+use crate::dragging::{DragKind, DraggedEvent, DropTarget, DroppedOntoEvent};
 use crate::gamestate::{GameStage, GameState};
-use crate::jam::JamIngredient;
-use crate::utils::average_colours;
+use crate::jam::{JamAssets, JamIngredient};
+use crate::settings::{Palette, Settings};
+use crate::shop_scene::PlayerScore;
+use crate::stations::ActiveStation;
+use crate::toss::{Ledge, Mouth};
+use crate::utils::mix_colours;
 use bevy::prelude::*;
 
 pub struct CauldronScenePlugin;
 
 struct Background;
-struct Cauldron;
+pub struct Cauldron;
 struct CauldronContent;
+/// the icon of an ingredient in the cauldron, for the colour-blind palettes
+struct ContentIcon;
 
 struct ReturnButton;
 struct ClearButton;
+struct HeatDownButton;
+struct HeatUpButton;
+struct BrewStatus;
+struct UpgradeButton;
+struct UpgradeLabel;
+
+/// text telling the player why something didn't work, cleared when the timer
+/// finishes
+struct Notice(Timer);
+
+/// an ingredient that boiled over, flying off the side of the cauldron
+struct Spill {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// ingredients the cauldron holds to begin with
+const BASE_CAPACITY: usize = 5;
+/// extra room each upgrade gives
+const UPGRADE_SIZE: usize = 2;
+/// score spent on the first upgrade, each one after costs this much more
+const UPGRADE_COST: u64 = 3;
+/// seconds a full cauldron can boil before it spills
+const SPILL_TIME: f32 = 3.0;
+/// how far the surface of the jam sits below the rim when it's empty
+const FILL_DROP: f32 = 40.0;
+/// the gap between the ingredient icons over the cauldron
+const ICON_SPACING: f32 = 30.0;
+/// where ingredients thrown into the cauldron go in, from its centre
+const MOUTH_HEIGHT: f32 = 40.0;
+const MOUTH_WIDTH: f32 = 220.0;
+/// what an empty cauldron splashes
+const EMPTY_SPLASH: Color = Color::rgb(0.6, 0.7, 0.8);
+/// the heights of the shelves the ingredients sit on, and how wide they are
+const SHELVES: [f32; 2] = [250.0, 170.0];
+const SHELF_WIDTH: f32 = 640.0;
 
 impl Plugin for CauldronScenePlugin {
     fn build(&self, app: &mut AppBuilder) {
@@ -27,6 +70,12 @@ impl Plugin for CauldronScenePlugin {
                 GameState::Cauldron,
                 recolour_cauldron.system(),
             )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                accept_ingredients.system(),
+            )
+            .on_state_update(GameStage::Main, GameState::Cauldron, warn_full.system())
             .on_state_update(
                 GameStage::Main,
                 GameState::Cauldron,
@@ -37,25 +86,111 @@ impl Plugin for CauldronScenePlugin {
                 GameState::Cauldron,
                 handle_clear_click.system(),
             )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                handle_heat_click.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                animate_cauldron.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                show_brew_status.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                show_fill_level.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                show_content_icons.system(),
+            )
+            .on_state_update(GameStage::Main, GameState::Cauldron, spawn_spills.system())
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                animate_spills.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                handle_upgrade_click.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                show_upgrade_label.system(),
+            )
+            .on_state_update(GameStage::Main, GameState::Cauldron, show_notices.system())
+            .on_state_update(GameStage::Main, GameState::Cauldron, clear_notice.system())
             .on_state_exit(GameStage::Main, GameState::Cauldron, teardown.system())
-            .insert_resource(CauldronContents(vec![]));
+            .add_system(boil_over.system())
+            .insert_resource(Spills::default())
+            .add_event::<NoticeEvent>();
     }
 }
 
 fn teardown(commands: &mut Commands, q_background: Query<Entity, With<Background>>) {
     for entity in q_background.iter() {
-        commands.despawn(entity);
+        commands.despawn_recursive(entity);
     }
 }
 
+/// what's in a station's cauldron, in the order it went in
 pub struct CauldronContents(pub Vec<JamIngredient>);
 
+impl CauldronContents {
+    /// the colour of everything mixed together, `None` when it's empty
+    pub fn colour(&self, palette: Palette) -> Option<Color> {
+        mix_colours(
+            self.0
+                .iter()
+                .map(|i| (i.colour(palette), i.colour_strength())),
+        )
+    }
+}
+
+pub struct CauldronCapacity {
+    pub size: usize,
+    upgrades: u64,
+}
+
+impl Default for CauldronCapacity {
+    fn default() -> Self {
+        CauldronCapacity {
+            size: BASE_CAPACITY,
+            upgrades: 0,
+        }
+    }
+}
+
+impl CauldronCapacity {
+    fn upgrade_cost(&self) -> u64 {
+        UPGRADE_COST * (self.upgrades + 1)
+    }
+}
+
+/// ingredients that boiled over the side of a full cauldron, cauldrons keep
+/// boiling while nobody's watching so these wait until their station is seen
+#[derive(Default)]
+struct Spills(Vec<(Entity, JamIngredient)>);
+
+/// tells the player why something in the cauldron scene didn't work
+pub struct NoticeEvent(pub String);
+
 fn setup(
     commands: &mut Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     let cauldron_bg_handle = asset_server.load("sprites/cauldron_back.png");
     let shop_front_shelf_handle = asset_server.load("sprites/frontshelf.png");
     let cauldron_top_handle = asset_server.load("sprites/cauldron_top.png");
@@ -66,6 +201,15 @@ fn setup(
         TextureAtlas::from_grid(cauldron_content_handle, Vec2::new(256.0, 256.0), 4, 1);
     let cauldron_content_atlas_handle = texture_atlases.add(cauldron_content_atlas);
 
+    for y in SHELVES.iter() {
+        commands.spawn((
+            Transform::from_xyz(0.0, *y, 0.0),
+            GlobalTransform::default(),
+            Ledge { width: SHELF_WIDTH },
+            Background,
+        ));
+    }
+
     commands
         .spawn(SpriteBundle {
             material: materials.add(cauldron_bg_handle.into()),
@@ -84,14 +228,20 @@ fn setup(
             ..Default::default()
         })
         .with(Background)
-        .with(DropTarget)
+        .with(DropTarget {
+            accepts: &[DragKind::Ingredient],
+        })
+        .with(Mouth {
+            offset: Vec2::new(0.0, MOUTH_HEIGHT),
+            width: MOUTH_WIDTH,
+            splash: EMPTY_SPLASH,
+        })
         .with(Cauldron)
         .spawn(SpriteSheetBundle {
             texture_atlas: cauldron_content_atlas_handle,
             transform: Transform::from_xyz(0.0, -150.0, 4.0),
             ..Default::default()
         })
-        .with(Timer::from_seconds(0.1, true))
         .with(Background)
         .with(CauldronContent)
         .spawn(ButtonBundle {
@@ -127,9 +277,135 @@ fn setup(
         })
         .with(button::ButtonState::default())
         .with(ClearButton)
+        .with(Background)
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                position: Rect {
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            ..Default::default()
+        })
+        .with(Background)
+        .with_children(|parent| {
+            button::spawn_text_button(parent, &mut *materials, font.clone(), "-", 20.0)
+                .with(HeatDownButton);
+
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(6.0)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 15.0,
+                            color: Color::WHITE,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .with(BrewStatus);
+
+            button::spawn_text_button(parent, &mut *materials, font.clone(), "+", 20.0)
+                .with(HeatUpButton);
+        })
+        .spawn(ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(20.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                },
+                padding: Rect::all(Val::Px(6.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::WHITE.into()),
+            ..Default::default()
+        })
+        .with(button::ButtonState::default())
+        .with(UpgradeButton)
+        .with(Background)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 15.0,
+                            color: Color::BLACK,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .with(UpgradeLabel);
+        })
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .with(Notice(Timer::from_seconds(2.5, false)))
         .with(Background);
 }
 
+fn upgrade_label(capacity: &CauldronCapacity) -> String {
+    format!(
+        "Bigger cauldron (+{}) - {} points",
+        UPGRADE_SIZE,
+        capacity.upgrade_cost()
+    )
+}
+
+fn show_notices(
+    mut q_notice: Query<(&mut Text, &mut Notice)>,
+    mut event_reader: EventReader<NoticeEvent>,
+) {
+    for NoticeEvent(message) in event_reader.iter() {
+        for (mut text, mut notice) in q_notice.iter_mut() {
+            text.sections[0].value = message.clone();
+            notice.0.reset();
+        }
+    }
+}
+
+fn clear_notice(time: Res<Time>, mut q_notice: Query<(&mut Text, &mut Notice)>) {
+    for (mut text, mut notice) in q_notice.iter_mut() {
+        if notice.0.tick(time.delta_seconds()).just_finished() {
+            text.sections[0].value.clear();
+        }
+    }
+}
+
 fn handle_return_click(
     mut state: ResMut<State<GameState>>,
     q_return: Query<&ReturnButton>,
@@ -143,22 +419,112 @@ fn handle_return_click(
 }
 
 fn handle_clear_click(
-    mut contents: ResMut<CauldronContents>,
+    active: Res<ActiveStation>,
+    mut q_station: Query<(&mut CauldronContents, &mut Brew)>,
     q_clear: Query<&ClearButton>,
     mut event_reader: EventReader<button::ButtonPressedEvent>,
 ) {
     for button::ButtonPressedEvent(entity) in event_reader.iter() {
         if let Ok(ClearButton) = q_clear.get_component(*entity) {
+            let (mut contents, mut brew) = q_station.get_mut(active.0).unwrap();
+
             contents.0.clear();
+            brew.reset();
+        }
+    }
+}
+
+fn handle_heat_click(
+    active: Res<ActiveStation>,
+    mut q_brew: Query<&mut Brew>,
+    q_down: Query<&HeatDownButton>,
+    q_up: Query<&HeatUpButton>,
+    mut event_reader: EventReader<button::ButtonPressedEvent>,
+) {
+    for button::ButtonPressedEvent(entity) in event_reader.iter() {
+        let mut brew = q_brew.get_mut(active.0).unwrap();
+
+        if let Ok(HeatDownButton) = q_down.get_component(*entity) {
+            brew.heat = brew.heat.saturating_sub(1);
+        }
+
+        if let Ok(HeatUpButton) = q_up.get_component(*entity) {
+            brew.heat = (brew.heat + 1).min(HEAT_STEPS);
+        }
+    }
+}
+
+fn show_brew_status(
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &CauldronCapacity, &Brew)>,
+    mut q_status: Query<&mut Text, With<BrewStatus>>,
+) {
+    let (contents, capacity, brew) = q_station.get(active.0).unwrap();
+    let state = brew.state(contents);
+    let status = format!(
+        "{}/{} full - Heat {}/{} - {} {:.0}% - Stirred {}/{}",
+        contents.0.len(),
+        capacity.size,
+        brew.heat,
+        HEAT_STEPS,
+        state.name(),
+        (brew.cooked * 100.0).min(100.0),
+        brew.stirs.min(brew.stirs_needed(contents)),
+        brew.stirs_needed(contents),
+    );
+
+    for mut text in q_status.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
+
+/// bubbles faster the hotter it is, and sits still off the heat
+fn animate_cauldron(
+    time: Res<Time>,
+    active: Res<ActiveStation>,
+    q_brew: Query<&Brew>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    mut since_frame: Local<f32>,
+    mut q_content: Query<(&mut TextureAtlasSprite, &Handle<TextureAtlas>), With<CauldronContent>>,
+) {
+    let brew = q_brew.get(active.0).unwrap();
+    let delay = match brew.bubble_delay() {
+        Some(delay) => delay,
+        None => return,
+    };
+
+    *since_frame += time.delta_seconds();
+    if *since_frame < delay {
+        return;
+    }
+    *since_frame = 0.0;
+
+    for (mut sprite, texture_atlas_handle) in q_content.iter_mut() {
+        if let Some(texture_atlas) = texture_atlases.get(texture_atlas_handle) {
+            sprite.index = ((sprite.index as usize + 1) % texture_atlas.textures.len()) as u32;
         }
     }
 }
 
 fn recolour_cauldron(
-    contents: Res<CauldronContents>,
+    settings: Res<Settings>,
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &Brew)>,
     mut q_content: Query<&mut TextureAtlasSprite, With<CauldronContent>>,
+    mut q_mouth: Query<&mut Mouth, With<Cauldron>>,
 ) {
-    let colour = average_colours(contents.0.iter().map(|i| i.colour()));
+    let (contents, brew) = q_station.get(active.0).unwrap();
+    let colour = contents
+        .colour(settings.palette)
+        .map(|colour| brew.tint(contents, colour));
+
+    for mut mouth in q_mouth.iter_mut() {
+        mouth.splash = colour.unwrap_or(EMPTY_SPLASH);
+    }
+
+    let colour = colour.unwrap_or(Color::NONE);
 
     for mut c in q_content.iter_mut() {
         c.color.set_r(colour.r());
@@ -168,8 +534,100 @@ fn recolour_cauldron(
     }
 }
 
+/// with a colour-blind palette the ingredients in the cauldron are shown as
+/// icons over it, so it doesn't all come down to the colour
+fn show_content_icons(
+    commands: &mut Commands,
+    mut shown: Local<Vec<JamIngredient>>,
+    settings: Res<Settings>,
+    active: Res<ActiveStation>,
+    jam_assets: Res<JamAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_contents: Query<&CauldronContents>,
+    q_icons: Query<Entity, With<ContentIcon>>,
+) {
+    let mut icons = vec![];
+    if settings.palette.colour_blind() {
+        for ingredient in &q_contents.get(active.0).unwrap().0 {
+            if !icons.contains(ingredient) {
+                icons.push(*ingredient);
+            }
+        }
+    }
+
+    if *shown == icons && (icons.is_empty() || q_icons.iter().next().is_some()) {
+        return;
+    }
+
+    for entity in q_icons.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    let middle = (icons.len() as f32 - 1.0) / 2.0;
+    for (idx, ingredient) in icons.iter().enumerate() {
+        let x = ICON_SPACING * (idx as f32 - middle);
+
+        commands
+            .spawn(SpriteBundle {
+                material: materials.add(ingredient.asset_for(&*jam_assets).into()),
+                transform: Transform {
+                    translation: Vec3::new(x, -60.0, 5.0),
+                    scale: Vec3::splat(0.4),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(Background)
+            .with(ContentIcon);
+    }
+
+    *shown = icons;
+}
+
+/// a full cauldron doesn't take any more, so ingredients dropped on it go back
+/// to the shelf
+fn accept_ingredients(
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &CauldronCapacity)>,
+    mut q_target: Query<&mut DropTarget, With<Cauldron>>,
+) {
+    let (contents, capacity) = q_station.get(active.0).unwrap();
+    let accepts: &'static [DragKind] = if contents.0.len() >= capacity.size {
+        &[]
+    } else {
+        &[DragKind::Ingredient]
+    };
+
+    for mut target in q_target.iter_mut() {
+        if target.accepts != accepts {
+            target.accepts = accepts;
+        }
+    }
+}
+
+/// picking up an ingredient while the cauldron is full says why it won't go in
+fn warn_full(
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &CauldronCapacity)>,
+    q_jam_ingredient: Query<&JamIngredient>,
+    mut ev_notice: ResMut<Events<NoticeEvent>>,
+    mut event_reader: EventReader<DraggedEvent>,
+) {
+    let (contents, capacity) = q_station.get(active.0).unwrap();
+
+    for DraggedEvent(entity) in event_reader.iter() {
+        if q_jam_ingredient.get(*entity).is_ok() && contents.0.len() >= capacity.size {
+            ev_notice.send(NoticeEvent(format!(
+                "The cauldron is full, it only holds {} ingredients",
+                capacity.size
+            )));
+        }
+    }
+}
+
 fn handle_content_drop(
-    mut contents: ResMut<CauldronContents>,
+    active: Res<ActiveStation>,
+    mut q_station: Query<(&mut CauldronContents, &CauldronCapacity, &mut Brew)>,
     q_cauldron: Query<&Cauldron>,
     q_jam_ingredient: Query<&JamIngredient>,
     mut event_reader: EventReader<DroppedOntoEvent>,
@@ -179,7 +637,144 @@ fn handle_content_drop(
             q_jam_ingredient.get_component::<JamIngredient>(*src),
             q_cauldron.get_component(*dst),
         ) {
+            let (mut contents, capacity, mut brew) = q_station.get_mut(active.0).unwrap();
+
+            // it stops accepting when full, this is in case it filled up since
+            if contents.0.len() >= capacity.size {
+                continue;
+            }
+
             contents.0.push(*ingredient);
+            brew.add_ingredient(contents.0.len());
+        }
+    }
+}
+
+fn handle_upgrade_click(
+    mut score: ResMut<PlayerScore>,
+    active: Res<ActiveStation>,
+    mut q_capacity: Query<&mut CauldronCapacity>,
+    q_upgrade: Query<&UpgradeButton>,
+    mut ev_notice: ResMut<Events<NoticeEvent>>,
+    mut event_reader: EventReader<button::ButtonPressedEvent>,
+) {
+    for button::ButtonPressedEvent(entity) in event_reader.iter() {
+        if let Ok(UpgradeButton) = q_upgrade.get_component(*entity) {
+            let mut capacity = q_capacity.get_mut(active.0).unwrap();
+            let cost = capacity.upgrade_cost();
+
+            if score.0 < cost {
+                ev_notice.send(NoticeEvent(format!(
+                    "You need {} points for a bigger cauldron",
+                    cost
+                )));
+                continue;
+            }
+
+            score.0 -= cost;
+            capacity.size += UPGRADE_SIZE;
+            capacity.upgrades += 1;
+        }
+    }
+}
+
+fn show_upgrade_label(
+    active: Res<ActiveStation>,
+    q_capacity: Query<&CauldronCapacity>,
+    mut q_label: Query<&mut Text, With<UpgradeLabel>>,
+) {
+    let label = upgrade_label(q_capacity.get(active.0).unwrap());
+
+    for mut text in q_label.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+/// the surface of the jam rises as the cauldron fills
+fn show_fill_level(
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &CauldronCapacity)>,
+    mut q_content: Query<&mut Transform, With<CauldronContent>>,
+) {
+    let (contents, capacity) = q_station.get(active.0).unwrap();
+    let fill = (contents.0.len() as f32 / capacity.size as f32).min(1.0);
+
+    for mut transform in q_content.iter_mut() {
+        transform.translation.y = -150.0 - FILL_DROP * (1.0 - fill);
+    }
+}
+
+/// a full cauldron left boiling loses the last thing put in it
+fn boil_over(
+    time: Res<Time>,
+    mut q_station: Query<(Entity, &mut CauldronContents, &CauldronCapacity, &mut Brew)>,
+    mut spills: ResMut<Spills>,
+) {
+    for (station, mut contents, capacity, mut brew) in q_station.iter_mut() {
+        let full = contents.0.len() >= capacity.size;
+
+        if !brew.boil_over(time.delta_seconds(), full, SPILL_TIME) {
+            continue;
+        }
+
+        if let Some(ingredient) = contents.0.pop() {
+            spills.0.push((station, ingredient));
+        }
+    }
+}
+
+fn spawn_spills(
+    commands: &mut Commands,
+    active: Res<ActiveStation>,
+    jam_assets: Res<JamAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut spills: ResMut<Spills>,
+) {
+    let (seen, unseen): (Vec<_>, Vec<_>) = spills
+        .0
+        .drain(..)
+        .partition(|(station, _)| *station == active.0);
+    spills.0 = unseen;
+
+    // fanned out, in case a few boiled over while we were away
+    for (idx, (_, ingredient)) in seen.into_iter().enumerate() {
+        commands
+            .spawn(SpriteBundle {
+                material: materials.add(ingredient.asset_for(&*jam_assets).into()),
+                transform: Transform::from_xyz(90.0, -90.0, 5.0),
+                ..Default::default()
+            })
+            .with(Spill {
+                velocity: Vec2::new(120.0 + 40.0 * idx as f32, 160.0),
+                timer: Timer::from_seconds(1.2, false),
+            })
+            .with(Background);
+    }
+}
+
+/// spilled ingredients arc off the rim and fade away
+fn animate_spills(
+    commands: &mut Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut q_spill: Query<(Entity, &mut Spill, &mut Transform, &Handle<ColorMaterial>)>,
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mut spill, mut transform, material) in q_spill.iter_mut() {
+        if spill.timer.tick(delta).finished() {
+            commands.despawn(entity);
+            continue;
+        }
+
+        spill.velocity.y -= 600.0 * delta;
+        transform.translation += spill.velocity.extend(0.0) * delta;
+
+        let alpha = 1.0 - spill.timer.percent();
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_a(alpha);
         }
     }
 }
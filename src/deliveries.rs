@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::jam::JamEffect;
+use crate::quests::QuestStepId;
+
+pub struct DeliveriesPlugin;
+
+impl Plugin for DeliveriesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(DeliveryHistory(vec![]))
+            .add_event::<CustomerLeftEvent>();
+    }
+}
+
+/// what happened with a single customer
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub quest: Option<QuestStepId>,
+    pub requirements: HashSet<JamEffect>,
+    /// effects of the jam the customer was given, `None` if they left without
+    /// getting one
+    pub delivered: Option<HashSet<JamEffect>>,
+    /// the jam had gone bad and made them sick
+    pub spoiled: bool,
+}
+
+impl Delivery {
+    pub fn met(&self) -> bool {
+        !self.spoiled
+            && self
+                .delivered
+                .as_ref()
+                .map_or(false, |effects| self.requirements.is_subset(effects))
+    }
+
+    /// spoiled jam doesn't do anything but make them sick
+    pub fn has_effect(&self, effect: JamEffect) -> bool {
+        !self.spoiled
+            && self
+                .delivered
+                .as_ref()
+                .map_or(false, |effects| effects.contains(&effect))
+    }
+}
+
+/// every customer that has left the shop, oldest first
+pub struct DeliveryHistory(pub Vec<Delivery>);
+
+impl DeliveryHistory {
+    pub fn for_arc(&self, arc: usize) -> impl Iterator<Item = &Delivery> {
+        self.0
+            .iter()
+            .filter(move |d| d.quest.as_ref().map_or(false, |q| q.arc == arc))
+    }
+}
+
+/// sent after the delivery has been added to the `DeliveryHistory`
+pub struct CustomerLeftEvent(pub Delivery);
+
+pub fn record(
+    history: &mut DeliveryHistory,
+    events: &mut Events<CustomerLeftEvent>,
+    delivery: Delivery,
+) {
+    history.0.push(delivery.clone());
+    events.send(CustomerLeftEvent(delivery));
+}
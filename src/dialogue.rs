@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::brewing::Brew;
+use crate::button;
+use crate::cauldron_scene::CauldronContents;
+use crate::deliveries::DeliveryHistory;
+use crate::gamestate::{GameStage, GameState};
+use crate::jam::{JamAssets, JamEffect, JamIngredient};
+use crate::quests::QuestLog;
+use crate::recipes::RecipeBook;
+use crate::settings::Settings;
+use crate::shop_scene::{CustomerArrivedEvent, Reputation, StoryAssets};
+use crate::speech_bubble::{Span, SpeechBubble};
+use crate::utils::load_data;
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup.system())
+            .on_state_enter(GameStage::Main, GameState::Main, mark_dirty.system())
+            .on_state_update(GameStage::Main, GameState::Main, start_dialogue.system())
+            .on_state_update(GameStage::Main, GameState::Main, handle_choice.system())
+            .on_state_update(GameStage::Main, GameState::Main, render_dialogue.system())
+            .on_state_update(GameStage::Main, GameState::Main, show_choices.system())
+            .on_state_exit(GameStage::Main, GameState::Main, teardown.system());
+    }
+}
+
+/// the speech bubble the conversation is shown in
+pub struct DialogueBox;
+
+/// colour for the parts of a story that hint at an effect
+const HIGHLIGHT: Color = Color::rgb(0.6, 0.1, 0.1);
+
+struct ChoiceRow;
+struct ChoiceButton(usize);
+
+#[derive(Deserialize)]
+struct DialogueTree {
+    start: String,
+    nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Deserialize)]
+struct DialogueNode {
+    /// `{story}` is replaced with the customer's story, `{hint}` with the last
+    /// effect they let slip
+    pages: Vec<String>,
+    #[serde(default)]
+    choices: Vec<DialogueChoice>,
+}
+
+#[derive(Deserialize)]
+struct DialogueChoice {
+    text: String,
+    #[serde(default)]
+    when: DialogueCondition,
+    /// seconds of the customer's patience this costs
+    #[serde(default)]
+    cost: f32,
+    /// the customer lets slip one of the effects they need
+    #[serde(default)]
+    reveal: bool,
+    /// where to go next, the conversation is over if there isn't one
+    #[serde(default)]
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+enum DialogueCondition {
+    Always,
+    /// a customer for every satisfied one before them, see `Reputation`
+    ReputationAtLeast(u64),
+    /// there are still effects the customer hasn't told us about
+    Unrevealed,
+    /// any of the stations has this in it
+    CauldronHas(JamIngredient),
+    /// any of the stations would make a jam with this effect
+    CauldronMakes(JamEffect),
+    /// the standing with the customer of the named quest arc is at least this,
+    /// see `QuestLog::standing`
+    StandingAtLeast(String, i32),
+    Not(Box<DialogueCondition>),
+    All(Vec<DialogueCondition>),
+    Any(Vec<DialogueCondition>),
+}
+
+impl Default for DialogueCondition {
+    fn default() -> Self {
+        DialogueCondition::Always
+    }
+}
+
+struct GameContext<'a> {
+    reputation: u64,
+    cauldrons: Vec<&'a [JamIngredient]>,
+    /// what the cauldrons would make if bottled now
+    brewing: HashSet<JamEffect>,
+    unrevealed: usize,
+    quest_log: &'a QuestLog,
+    history: &'a DeliveryHistory,
+}
+
+impl<'a> GameContext<'a> {
+    fn new(
+        dialogue: &Dialogue,
+        reputation: &Reputation,
+        recipes: &RecipeBook,
+        story: &StoryAssets,
+        quest_log: &'a QuestLog,
+        history: &'a DeliveryHistory,
+        stations: impl Iterator<Item = (&'a CauldronContents, &'a Brew)>,
+    ) -> Self {
+        let stations: Vec<(&CauldronContents, &Brew)> = stations.collect();
+
+        GameContext {
+            reputation: reputation.0,
+            cauldrons: stations.iter().map(|&(c, _)| &c.0[..]).collect(),
+            brewing: stations
+                .iter()
+                .flat_map(|&(c, b)| b.effects(c, recipes))
+                .collect(),
+            unrevealed: story
+                .requirements()
+                .iter()
+                .filter(|e| !dialogue.revealed.contains(*e))
+                .count(),
+            quest_log,
+            history,
+        }
+    }
+}
+
+impl DialogueCondition {
+    fn holds(&self, ctx: &GameContext) -> bool {
+        match self {
+            DialogueCondition::Always => true,
+            DialogueCondition::ReputationAtLeast(reputation) => ctx.reputation >= *reputation,
+            DialogueCondition::Unrevealed => ctx.unrevealed > 0,
+            DialogueCondition::CauldronHas(ingredient) => {
+                ctx.cauldrons.iter().any(|c| c.contains(ingredient))
+            }
+            DialogueCondition::CauldronMakes(effect) => ctx.brewing.contains(effect),
+            DialogueCondition::StandingAtLeast(arc, standing) => {
+                ctx.quest_log.standing(arc, ctx.history).unwrap() >= *standing
+            }
+            DialogueCondition::Not(c) => !c.holds(ctx),
+            DialogueCondition::All(cs) => cs.iter().all(|c| c.holds(ctx)),
+            DialogueCondition::Any(cs) => cs.iter().any(|c| c.holds(ctx)),
+        }
+    }
+
+    /// panics if it names a quest arc that doesn't exist
+    fn validate(&self, quest_log: &QuestLog) {
+        match self {
+            DialogueCondition::StandingAtLeast(arc, _) => {
+                if !quest_log.has_arc(arc) {
+                    panic!("dialogue condition names missing quest arc '{}'", arc);
+                }
+            }
+            DialogueCondition::Not(c) => c.validate(quest_log),
+            DialogueCondition::All(cs) | DialogueCondition::Any(cs) => {
+                for c in cs {
+                    c.validate(quest_log);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Dialogue {
+    tree: DialogueTree,
+    /// `None` while showing text that isn't part of the tree, like the welcome
+    node: Option<String>,
+    /// filled in when they're shown, so they pick up newly revealed effects
+    pages: Vec<String>,
+    revealed: Vec<JamEffect>,
+    /// the text box needs refilling and the choices respawning
+    dirty: bool,
+    /// whether the story in the text box was filled with keywords highlighted
+    highlighted: bool,
+    /// choices are only offered once the customer has finished talking
+    choices_shown: bool,
+}
+
+impl Dialogue {
+    fn goto(&mut self, node: Option<String>) {
+        self.pages = match node.as_ref().and_then(|n| self.tree.nodes.get(n)) {
+            Some(n) => n.pages.clone(),
+            None => self.pages.last().cloned().into_iter().collect(),
+        };
+        self.node = node;
+        self.dirty = true;
+    }
+
+    /// with highlighting on, the parts of the story that hint at an effect are
+    /// coloured and tagged with its icon. `None` for a page with a `{hint}`
+    /// when nothing has been revealed yet
+    fn fill(
+        &self,
+        page: &str,
+        story: &StoryAssets,
+        jam_assets: &JamAssets,
+        highlight: bool,
+    ) -> Option<Vec<Span>> {
+        let page = match self.revealed.last() {
+            Some(hint) => page.replace("{hint}", hint.name()),
+            None if page.contains("{hint}") => return None,
+            None => page.to_string(),
+        };
+
+        let mut parts = page.split("{story}");
+        let mut spans: Vec<Span> = parts.next().map(Span::plain).into_iter().collect();
+
+        for part in parts {
+            spans.extend(story.story().iter().map(|segment| {
+                let effect = segment.effect.filter(|_| highlight);
+                Span {
+                    text: segment.text.clone(),
+                    colour: effect.map(|_| HIGHLIGHT),
+                    icon: effect.map(|e| e.asset_for(jam_assets)),
+                }
+            }));
+            spans.push(Span::plain(part));
+        }
+
+        Some(spans)
+    }
+
+    fn choices(&self) -> &[DialogueChoice] {
+        match self.node.as_ref().and_then(|n| self.tree.nodes.get(n)) {
+            Some(node) => &node.choices[..],
+            None => &[],
+        }
+    }
+}
+
+fn setup(commands: &mut Commands, quest_log: Res<QuestLog>) {
+    let tree: DialogueTree = load_data("dialogue.ron");
+
+    for (name, node) in &tree.nodes {
+        for choice in &node.choices {
+            choice.when.validate(&*quest_log);
+        }
+
+        for choice in &node.choices {
+            let next = match &choice.next {
+                Some(next) => next,
+                None => continue,
+            };
+            let hints = match tree.nodes.get(next) {
+                Some(n) => n.pages.iter().any(|p| p.contains("{hint}")),
+                None => panic!("dialogue node '{}' leads to missing node '{}'", name, next),
+            };
+            // otherwise there may be nothing to fill the hint in with
+            if hints && !choice.reveal {
+                panic!(
+                    "dialogue choice '{}' leads to a hint in '{}' without revealing one",
+                    choice.text, next
+                );
+            }
+        }
+    }
+
+    commands.insert_resource(Dialogue {
+        tree,
+        node: None,
+        pages: vec!["{story}".to_string()],
+        revealed: vec![],
+        dirty: true,
+        highlighted: false,
+        choices_shown: false,
+    });
+}
+
+fn teardown(commands: &mut Commands, q_choices: Query<Entity, With<ChoiceRow>>) {
+    for entity in q_choices.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+fn mark_dirty(mut dialogue: ResMut<Dialogue>) {
+    dialogue.dirty = true;
+}
+
+fn start_dialogue(
+    mut dialogue: ResMut<Dialogue>,
+    mut event_reader: EventReader<CustomerArrivedEvent>,
+) {
+    for CustomerArrivedEvent in event_reader.iter() {
+        dialogue.revealed.clear();
+
+        let start = dialogue.tree.start.clone();
+        dialogue.goto(Some(start));
+    }
+}
+
+fn handle_choice(
+    mut dialogue: ResMut<Dialogue>,
+    mut story: ResMut<StoryAssets>,
+    reputation: Res<Reputation>,
+    recipes: Res<RecipeBook>,
+    quest_log: Res<QuestLog>,
+    history: Res<DeliveryHistory>,
+    q_station: Query<(&CauldronContents, &Brew)>,
+    q_choice: Query<&ChoiceButton>,
+    mut event_reader: EventReader<button::ButtonPressedEvent>,
+) {
+    for button::ButtonPressedEvent(entity) in event_reader.iter() {
+        if let Ok(ChoiceButton(idx)) = q_choice.get_component(*entity) {
+            let ctx = GameContext::new(
+                &*dialogue,
+                &*reputation,
+                &*recipes,
+                &*story,
+                &*quest_log,
+                &*history,
+                q_station.iter(),
+            );
+
+            // things may have changed since the choice was shown
+            let choice = match dialogue.choices().get(*idx) {
+                Some(choice) if choice.when.holds(&ctx) => choice,
+                _ => continue,
+            };
+
+            let (cost, reveal, next) = (choice.cost, choice.reveal, choice.next.clone());
+
+            story.spend_patience(cost);
+
+            if reveal {
+                let unrevealed: Vec<JamEffect> = story
+                    .requirements()
+                    .iter()
+                    .filter(|e| !dialogue.revealed.contains(*e))
+                    .cloned()
+                    .collect();
+
+                if let Some(effect) = unrevealed.choose(&mut rand::thread_rng()) {
+                    dialogue.revealed.push(*effect);
+                }
+            }
+
+            dialogue.goto(next);
+        }
+    }
+}
+
+fn render_dialogue(
+    commands: &mut Commands,
+    mut dialogue: ResMut<Dialogue>,
+    settings: Res<Settings>,
+    story: Res<StoryAssets>,
+    jam_assets: Res<JamAssets>,
+    mut q_box: Query<&mut SpeechBubble, With<DialogueBox>>,
+    q_choices: Query<Entity, With<ChoiceRow>>,
+) {
+    if !dialogue.dirty && dialogue.highlighted == settings.highlight_keywords {
+        return;
+    }
+
+    dialogue.dirty = false;
+    dialogue.choices_shown = false;
+    dialogue.highlighted = settings.highlight_keywords;
+
+    let pages: Vec<Vec<Span>> = dialogue
+        .pages
+        .iter()
+        .filter_map(|page| dialogue.fill(page, &*story, &*jam_assets, settings.highlight_keywords))
+        .collect();
+
+    for mut bubble in q_box.iter_mut() {
+        bubble.set_pages(pages.clone());
+    }
+
+    for entity in q_choices.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+fn show_choices(
+    commands: &mut Commands,
+    mut dialogue: ResMut<Dialogue>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    reputation: Res<Reputation>,
+    recipes: Res<RecipeBook>,
+    story: Res<StoryAssets>,
+    quest_log: Res<QuestLog>,
+    history: Res<DeliveryHistory>,
+    q_station: Query<(&CauldronContents, &Brew)>,
+    q_box: Query<&SpeechBubble, With<DialogueBox>>,
+) {
+    if dialogue.choices_shown || !q_box.iter().all(|bubble| bubble.finished()) {
+        return;
+    }
+
+    dialogue.choices_shown = true;
+
+    let ctx = GameContext::new(
+        &*dialogue,
+        &*reputation,
+        &*recipes,
+        &*story,
+        &*quest_log,
+        &*history,
+        q_station.iter(),
+    );
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                position: Rect {
+                    bottom: Val::Px(105.0),
+                    left: Val::Px(140.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .with(ChoiceRow)
+        .with_children(|parent| {
+            for (idx, choice) in dialogue.choices().iter().enumerate() {
+                if !choice.when.holds(&ctx) {
+                    continue;
+                }
+
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            margin: Rect::all(Val::Px(4.0)),
+                            padding: Rect::all(Val::Px(4.0)),
+                            ..Default::default()
+                        },
+                        material: materials.add(Color::WHITE.into()),
+                        ..Default::default()
+                    })
+                    .with(button::ButtonState::default())
+                    .with(ChoiceButton(idx))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle {
+                            text: Text::with_section(
+                                choice.text.clone(),
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: 13.0,
+                                    color: Color::BLACK,
+                                },
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
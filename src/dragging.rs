@@ -1,4 +1,9 @@
-use bevy::{prelude::*, render::camera::Camera};
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+
+use crate::alpha_mask::AlphaMasks;
+use crate::viewport::WorldCamera;
 
 // https://stackoverflow.com/a/65401648
 
@@ -6,17 +11,24 @@ pub struct DragPlugin;
 
 impl Plugin for DragPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(setup.system())
+        app.insert_resource(DragSettings::default())
+            .add_startup_system(setup.system())
             .add_system_to_stage(CoreStage::PreUpdate, cursor_state.system())
             .add_system_to_stage(CoreStage::Update, draggable.system())
             .add_system_to_stage(CoreStage::Update, hoverable.system())
             .add_system_to_stage(CoreStage::PostUpdate, drag.system())
             .add_system_to_stage(CoreStage::PostUpdate, drop.system())
+            .add_system_to_stage(CoreStage::PostUpdate, land.system())
+            .add_system_to_stage(CoreStage::PostUpdate, settle.system())
+            .add_system_to_stage(CoreStage::PostUpdate, drop_orphans.system())
             .add_system_to_stage(CoreStage::PostUpdate, material.system())
             .add_system_to_stage(CoreStage::PostUpdate, material_atlas.system())
             .add_event::<DraggedEvent>()
             .add_event::<DroppedEvent>()
-            .add_event::<DroppedOntoEvent>();
+            .add_event::<DroppedOntoEvent>()
+            .add_event::<TossedEvent>()
+            .add_event::<PointAtEvent>()
+            .add_event::<GrabEvent>();
     }
 }
 
@@ -29,28 +41,136 @@ fn setup(commands: &mut Commands) {
 }
 
 #[derive(Default)]
-struct CursorState {
+pub struct CursorState {
     cursor_world: Vec2,
     cursor_offset: Vec2,
     cursor_moved: bool,
+    cursor_velocity: Vec2,
+}
+
+impl CursorState {
+    /// where the cursor is in world coordinates
+    pub fn world(&self) -> Vec2 {
+        self.cursor_world
+    }
+
+    /// whether the cursor moved this frame
+    pub fn moved(&self) -> bool {
+        self.cursor_moved
+    }
+
+    /// how fast the cursor is going in world units per second, smoothed over
+    /// the last few frames
+    pub fn velocity(&self) -> Vec2 {
+        self.cursor_velocity
+    }
 }
 
 struct Cursor;
 
-pub struct Draggable;
+/// moves the cursor to a point in the world, for pointing with something
+/// other than the mouse. a `jump` doesn't count towards the cursor's velocity
+pub struct PointAtEvent {
+    pub world: Vec2,
+    pub jump: bool,
+}
+
+/// picks up or lets go, for grabbing with something other than the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrabEvent {
+    Pressed,
+    Released,
+}
+
+/// seconds a missed drop takes to go back where it was picked up
+const RETURN_TIME: f32 = 0.25;
+/// seconds an accepted drop takes to be taken in by its target
+const ABSORB_TIME: f32 = 0.3;
+/// how small an accepted drop shrinks to before it's gone
+const ABSORB_SCALE: f32 = 0.2;
+/// how much each frame's cursor movement counts towards its velocity
+const VELOCITY_WEIGHT: f32 = 0.5;
+/// a `Throwable` let go of faster than this, in world units per second, is
+/// thrown rather than dropped, unless it's let go over something that takes it
+const TOSS_SPEED: f32 = 400.0;
+
+pub struct DragSettings {
+    /// whether drops move back home or into their target, rather than jumping
+    pub animate_drops: bool,
+}
+
+impl Default for DragSettings {
+    fn default() -> Self {
+        DragSettings {
+            animate_drops: true,
+        }
+    }
+}
+
+/// the sorts of things that can be picked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragKind {
+    Ingredient,
+    Jar,
+}
+
+pub struct Draggable(pub DragKind);
 pub struct Dragged;
+/// sent with what was picked up, the spawner rather than its copy
 pub struct DraggedEvent(pub Entity);
 struct Dropped;
 
+/// a draggable that stays put, dragging it picks up a copy instead, like
+/// taking one ingredient from a pile
+pub struct Spawner;
+/// a copy picked up from the spawner
+struct Proxy(Entity);
+/// where a dragged item was picked up
+struct Origin(Vec3);
+
+/// can be thrown by letting go of it while moving, for a `Spawner` this goes
+/// on the spawner
+pub struct Throwable;
+/// something let go of fast enough to be thrown, whatever moves it adds
+/// `Landed` when it comes down
+pub struct TossedEvent {
+    pub entity: Entity,
+    pub velocity: Vec2,
+}
+/// a thrown item has come down, onto the target if it's `Some`
+pub struct Landed(pub Option<Entity>);
+/// thrown and not yet landed
+struct Airborne;
+
+/// a dropped item on its way home, or into the target that took it
+struct Settling {
+    from: Vec3,
+    to: Vec3,
+    scale: Vec3,
+    timer: Timer,
+    absorb: bool,
+}
+
 pub struct Hoverable;
 pub struct Hovered;
 
-pub struct DropTarget;
+/// something that things can be dropped onto, only the kinds it accepts land
+/// on it
+pub struct DropTarget {
+    pub accepts: &'static [DragKind],
+}
+
+impl DropTarget {
+    pub fn will_accept(&self, kind: DragKind) -> bool {
+        self.accepts.contains(&kind)
+    }
+}
 
 pub struct DroppedEvent(pub Entity);
 
 /// component added to both the src and dst entities when src is dropped onto
-/// dst
+/// dst, only sent when dst accepts src. for a copy from a `Spawner`, src is
+/// the spawner
 pub struct DroppedOntoEvent {
     pub src: Entity,
     pub dst: Entity,
@@ -58,13 +178,19 @@ pub struct DroppedOntoEvent {
 
 fn cursor_state(
     mut cursor_events: EventReader<CursorMoved>,
+    mut ev_point_at: EventReader<PointAtEvent>,
+    time: Res<Time>,
     windows: Res<Windows>,
     mut q_cursor_state: Query<&mut CursorState>,
-    q_camera: Query<&Transform, With<Camera>>,
+    q_camera: Query<&Transform, With<WorldCamera>>,
 ) {
-    let event_cursor_screen = cursor_events.iter().next();
+    let event_cursor_screen = cursor_events.iter().last();
+    let pointed_at = ev_point_at.iter().last();
 
     for mut cursor_state in q_cursor_state.iter_mut() {
+        let before = cursor_state.cursor_world;
+        let mut jumped = false;
+
         if let Some(event_cursor_screen) = event_cursor_screen {
             let window = windows.get_primary().unwrap();
             let cam_transform = q_camera.iter().last().unwrap();
@@ -72,86 +198,220 @@ fn cursor_state(
                 cursor_to_world(window, cam_transform, event_cursor_screen.position);
 
             cursor_state.cursor_moved = true;
+        } else if let Some(PointAtEvent { world, jump }) = pointed_at {
+            cursor_state.cursor_world = *world;
+            cursor_state.cursor_moved = true;
+            jumped = *jump;
         } else {
             cursor_state.cursor_moved = false;
         }
+
+        if jumped {
+            cursor_state.cursor_velocity = Vec2::zero();
+        } else if time.delta_seconds() > 0.0 {
+            let velocity = (cursor_state.cursor_world - before) / time.delta_seconds();
+            cursor_state.cursor_velocity =
+                cursor_state.cursor_velocity.lerp(velocity, VELOCITY_WEIGHT);
+        }
+    }
+}
+
+/// the assets needed to work out where a sprite can be clicked
+struct SpriteAssets<'a> {
+    atlases: &'a Assets<TextureAtlas>,
+    materials: &'a Assets<ColorMaterial>,
+}
+
+/// where a sprite is in the world and what's drawn there
+struct Hitbox {
+    /// maps the world into the sprite, its centre at the origin
+    to_local: Mat4,
+    /// the sprite's z in the world, higher is drawn on top
+    depth: f32,
+    /// before any scaling
+    size: Vec2,
+    texture: Option<Handle<Texture>>,
+    /// the frame of the texture that's drawn, for sprite sheets
+    frame: Option<(Vec2, Vec2)>,
+}
+
+impl Hitbox {
+    /// `None` until the sprite's sheet has loaded
+    fn new(
+        global: &GlobalTransform,
+        sprite: Option<&Sprite>,
+        material: Option<&Handle<ColorMaterial>>,
+        atlas_sprite: Option<(&TextureAtlasSprite, &Handle<TextureAtlas>)>,
+        assets: &SpriteAssets,
+    ) -> Option<Self> {
+        let (size, texture, frame) = if let Some(sprite) = sprite {
+            let texture = material
+                .and_then(|m| assets.materials.get(m))
+                .and_then(|m| m.texture.clone());
+
+            (sprite.size, texture, None)
+        } else {
+            let (atlas_sprite, handle) = atlas_sprite?;
+            let atlas = assets.atlases.get(handle)?;
+            let frame = atlas.textures.get(atlas_sprite.index as usize)?;
+            let size = Vec2::new(frame.width(), frame.height());
+
+            (size, Some(atlas.texture.clone()), Some((frame.min, size)))
+        };
+
+        Some(Hitbox::at(global, size, texture, frame))
+    }
+
+    /// a sprite of `size` placed by `global`, which takes in the scale,
+    /// rotation and parents of the sprite
+    fn at(
+        global: &GlobalTransform,
+        size: Vec2,
+        texture: Option<Handle<Texture>>,
+        frame: Option<(Vec2, Vec2)>,
+    ) -> Self {
+        Hitbox {
+            to_local: global.compute_matrix().inverse(),
+            depth: global.translation.z,
+            size,
+            texture,
+            frame,
+        }
     }
+
+    /// whether a solid part of the sprite is at `point`
+    fn contains(&self, point: Vec2, masks: &AlphaMasks) -> bool {
+        let offset = self.to_local * point.extend(self.depth).extend(1.0);
+        if offset.x.abs() >= self.size.x / 2.0 || offset.y.abs() >= self.size.y / 2.0 {
+            return false;
+        }
+
+        // textures go down from the top left, the world goes up from the middle
+        let uv = Vec2::new(0.5 + offset.x / self.size.x, 0.5 - offset.y / self.size.y);
+
+        match &self.texture {
+            Some(texture) => masks.solid(texture, self.frame, uv),
+            None => true,
+        }
+    }
+}
+
+/// the candidate under `point` that's drawn on top, by z in the world. ties go
+/// to the higher entity id so the pick doesn't depend on the query's order
+fn topmost<I: IntoIterator<Item = (Entity, Hitbox)>>(
+    point: Vec2,
+    candidates: I,
+    masks: &AlphaMasks,
+) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .filter(|(_, hitbox)| hitbox.contains(point, masks))
+        .max_by(|(a, a_box), (b, b_box)| {
+            a_box
+                .depth
+                .partial_cmp(&b_box.depth)
+                .unwrap_or(Ordering::Equal)
+                .then(a.id().cmp(&b.id()))
+        })
+        .map(|(entity, _)| entity)
 }
 
 fn hoverable(
     commands: &mut Commands,
-    atlas_assets: Res<Assets<TextureAtlas>>,
+    atlases: Res<Assets<TextureAtlas>>,
+    materials: Res<Assets<ColorMaterial>>,
+    masks: Res<AlphaMasks>,
     q_cursor_state: Query<&CursorState>,
-    q_is_dragged: Query<(), With<Dragged>>,
+    q_dragged: Query<&Draggable, With<Dragged>>,
     q_hoverable: Query<
         (
             Entity,
-            &Transform,
+            &GlobalTransform,
             Option<&Sprite>,
-            Option<&Handle<TextureAtlas>>,
+            Option<&Handle<ColorMaterial>>,
+            Option<(&TextureAtlasSprite, &Handle<TextureAtlas>)>,
             Option<&Hoverable>,
             Option<&DropTarget>,
+            Option<&Hovered>,
         ),
         (
             Or<(With<Hoverable>, With<DropTarget>)>,
             Or<(With<Sprite>, With<Handle<TextureAtlas>>)>,
             Without<Dragged>,
+            Without<Settling>,
+            Without<Airborne>,
         ),
     >,
 ) {
     let cursor_state = q_cursor_state.iter().next().unwrap();
-    let is_dragged = q_is_dragged.iter().next().is_some();
-
-    if cursor_state.cursor_moved {
-        for (entity, transform, sprite, t_atlas, is_hoverable, is_drop_target) in q_hoverable.iter()
-        {
-            if !is_hoverable.is_some() && is_drop_target.is_some() && !is_dragged {
-                // ignore non-hoverable targets if we're not dragging
-                commands.remove_one::<Hovered>(entity);
-                continue;
-            }
-
-            let (x, y) = if let Some(sprite) = sprite {
-                (sprite.size.x, sprite.size.y)
-            } else if let Some(t_atlas) = t_atlas {
-                let atlas = atlas_assets.get(t_atlas).unwrap();
+    let dragged = q_dragged.iter().next().map(|Draggable(kind)| *kind);
 
-                (atlas.size.x, atlas.size.y)
-            } else {
-                unreachable!()
-            };
-
-            let half_width = x / 2.0;
-            let half_height = y / 2.0;
+    if !cursor_state.cursor_moved {
+        return;
+    }
 
-            if transform.translation.x - half_width < cursor_state.cursor_world.x
-                && transform.translation.x + half_width > cursor_state.cursor_world.x
-                && transform.translation.y - half_height < cursor_state.cursor_world.y
-                && transform.translation.y + half_height > cursor_state.cursor_world.y
-            {
+    let assets = SpriteAssets {
+        atlases: &*atlases,
+        materials: &*materials,
+    };
+
+    // while dragging only the targets that would take it count
+    let candidates = q_hoverable
+        .iter()
+        .filter(|(_, _, _, _, _, is_hoverable, target, _)| match dragged {
+            Some(kind) => target.map_or(false, |t| t.will_accept(kind)),
+            None => is_hoverable.is_some(),
+        })
+        .filter_map(
+            |(entity, global, sprite, material, atlas_sprite, _, _, _)| {
+                let hitbox = Hitbox::new(global, sprite, material, atlas_sprite, &assets)?;
+                Some((entity, hitbox))
+            },
+        );
+    let top = topmost(cursor_state.cursor_world, candidates, &*masks);
+
+    for (entity, _, _, _, _, _, _, hovered) in q_hoverable.iter() {
+        if Some(entity) == top {
+            if hovered.is_none() {
                 commands.insert_one(entity, Hovered);
-            } else {
-                commands.remove_one::<Hovered>(entity);
             }
+        } else if hovered.is_some() {
+            commands.remove_one::<Hovered>(entity);
         }
     }
 }
 
+/// the red, green and alpha a sprite is tinted with. while something is being
+/// dragged, `target` is whether this would accept it, if it's a drop target
+fn tint(hovered: bool, dragged: bool, target: Option<bool>) -> (f32, f32, f32) {
+    match (target, hovered) {
+        (Some(true), true) => (0.3, 1.0, 1.0),
+        (Some(true), false) => (0.7, 1.0, 1.0),
+        (Some(false), _) => (1.0, 0.4, 0.6),
+        (None, _) if dragged => (0.0, 1.0, 1.0),
+        (None, true) => (1.0, 0.0, 1.0),
+        (None, false) => (1.0, 1.0, 1.0),
+    }
+}
+
 fn material(
     mut materials: ResMut<Assets<ColorMaterial>>,
+    q_dragged: Query<&Draggable, With<Dragged>>,
     q_hoverable: Query<
-        (&Handle<ColorMaterial>, Option<&Hovered>, Option<&Dragged>),
-        Or<(With<Hoverable>, With<DropTarget>)>,
+        (
+            &Handle<ColorMaterial>,
+            Option<&Hovered>,
+            Option<&Dragged>,
+            Option<&DropTarget>,
+        ),
+        Or<(With<Hoverable>, With<DropTarget>, With<Dragged>)>,
     >,
 ) {
-    for (material, hovered, dragged) in q_hoverable.iter() {
-        let (red, green, alpha) = if dragged.is_some() {
-            (0.0, 1.0, 1.0)
-        } else if hovered.is_some() {
-            (1.0, 0.0, 1.0)
-        } else {
-            (1.0, 1.0, 1.0)
-        };
+    let kind = q_dragged.iter().next().map(|Draggable(kind)| *kind);
+
+    for (material, hovered, dragged, target) in q_hoverable.iter() {
+        let target = kind.and_then(|kind| Some(target?.will_accept(kind)));
+        let (red, green, alpha) = tint(hovered.is_some(), dragged.is_some(), target);
 
         if let Some(mat) = materials.get_mut(material) {
             mat.color.set_r(red);
@@ -162,19 +422,22 @@ fn material(
 }
 
 fn material_atlas(
+    q_dragged: Query<&Draggable, With<Dragged>>,
     mut q_hoverable: Query<
-        (&mut TextureAtlasSprite, Option<&Hovered>, Option<&Dragged>),
-        Or<(With<Hoverable>, With<DropTarget>)>,
+        (
+            &mut TextureAtlasSprite,
+            Option<&Hovered>,
+            Option<&Dragged>,
+            Option<&DropTarget>,
+        ),
+        Or<(With<Hoverable>, With<DropTarget>, With<Dragged>)>,
     >,
 ) {
-    for (mut material, hovered, dragged) in q_hoverable.iter_mut() {
-        let (red, green, alpha) = if dragged.is_some() {
-            (0.0, 1.0, 1.0)
-        } else if hovered.is_some() {
-            (1.0, 0.0, 1.0)
-        } else {
-            (1.0, 1.0, 1.0)
-        };
+    let kind = q_dragged.iter().next().map(|Draggable(kind)| *kind);
+
+    for (mut material, hovered, dragged, target) in q_hoverable.iter_mut() {
+        let target = kind.and_then(|kind| Some(target?.will_accept(kind)));
+        let (red, green, alpha) = tint(hovered.is_some(), dragged.is_some(), target);
 
         material.color.set_r(red);
         material.color.set_g(green);
@@ -182,6 +445,8 @@ fn material_atlas(
     }
 }
 
+/// the camera is scaled to fit the game into the window, so this holds at any
+/// window size, and as the sizes are in logical pixels, any dpi
 fn cursor_to_world(window: &Window, cam_transform: &Transform, cursor_pos: Vec2) -> Vec2 {
     // get the size of the window
     let size = Vec2::new(window.width() as f32, window.height() as f32);
@@ -195,24 +460,93 @@ fn cursor_to_world(window: &Window, cam_transform: &Transform, cursor_pos: Vec2)
     Vec2::new(out.x, out.y)
 }
 
+/// a copy of a spawner to drag, with its own material so tinting it leaves
+/// the spawner alone. drawn just above the spawner
+fn spawn_proxy(
+    commands: &mut Commands,
+    materials: &mut Assets<ColorMaterial>,
+    spawner: Entity,
+    kind: DragKind,
+    transform: &Transform,
+    sprite: Option<&Sprite>,
+    material: Option<&Handle<ColorMaterial>>,
+) -> Entity {
+    let material = material
+        .and_then(|m| materials.get(m))
+        .map(|m| ColorMaterial {
+            color: m.color,
+            texture: m.texture.clone(),
+        })
+        .unwrap_or_default();
+
+    let mut transform = *transform;
+    transform.translation.z += 0.5;
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: sprite.cloned().unwrap_or_default(),
+            material: materials.add(material),
+            transform,
+            ..Default::default()
+        })
+        .with(Draggable(kind))
+        .with(Proxy(spawner))
+        .current_entity()
+        .unwrap()
+}
+
 fn draggable(
     commands: &mut Commands,
     i_mouse_button: Res<Input<MouseButton>>,
+    mut ev_grab: EventReader<GrabEvent>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
     mut ev_dragged: ResMut<Events<DraggedEvent>>,
     mut q_cursor_state: Query<&mut CursorState>,
-    q_pressed: Query<(Entity, &Transform), (With<Hovered>, With<Draggable>)>,
+    q_pressed: Query<
+        (
+            Entity,
+            &Transform,
+            &Draggable,
+            Option<&Spawner>,
+            Option<&Sprite>,
+            Option<&Handle<ColorMaterial>>,
+        ),
+        (With<Hovered>, Without<Settling>, Without<Airborne>),
+    >,
     q_released: Query<Entity, With<Dragged>>,
 ) {
-    if i_mouse_button.just_pressed(MouseButton::Left) {
-        if let Some((entity, transform)) = q_pressed.iter().next() {
+    let grabs: Vec<GrabEvent> = ev_grab.iter().copied().collect();
+    let pressed =
+        i_mouse_button.just_pressed(MouseButton::Left) || grabs.contains(&GrabEvent::Pressed);
+    let released =
+        i_mouse_button.just_released(MouseButton::Left) || grabs.contains(&GrabEvent::Released);
+
+    if pressed {
+        if let Some((entity, transform, Draggable(kind), spawner, sprite, material)) =
+            q_pressed.iter().next()
+        {
             let mut cursor_state = q_cursor_state.iter_mut().next().unwrap();
             cursor_state.cursor_offset =
                 transform.translation.truncate() - cursor_state.cursor_world;
 
+            let dragged = match spawner {
+                Some(Spawner) => spawn_proxy(
+                    commands,
+                    &mut *materials,
+                    entity,
+                    *kind,
+                    transform,
+                    sprite,
+                    material,
+                ),
+                None => entity,
+            };
+
             ev_dragged.send(DraggedEvent(entity));
-            commands.insert_one(entity, Dragged);
+            commands.insert_one(dragged, Dragged);
+            commands.insert_one(dragged, Origin(transform.translation));
         }
-    } else if i_mouse_button.just_released(MouseButton::Left) {
+    } else if released {
         for entity in q_released.iter() {
             commands.remove_one::<Dragged>(entity);
 
@@ -234,62 +568,323 @@ fn drag(q_cursor_state: Query<&CursorState>, mut q_dragged: Query<&mut Transform
 
 fn drop(
     commands: &mut Commands,
-    atlas_assets: Res<Assets<TextureAtlas>>,
+    atlases: Res<Assets<TextureAtlas>>,
+    materials: Res<Assets<ColorMaterial>>,
+    masks: Res<AlphaMasks>,
     mut ev_dropped_onto: ResMut<Events<DroppedOntoEvent>>,
+    settings: Res<DragSettings>,
     mut ev_dropped: ResMut<Events<DroppedEvent>>,
-    mut q_dropped: Query<Entity, Added<Dropped>>,
+    mut ev_tossed: ResMut<Events<TossedEvent>>,
+    mut q_dropped: Query<
+        (
+            Entity,
+            &Draggable,
+            &Transform,
+            Option<&Origin>,
+            Option<&Proxy>,
+        ),
+        Added<Dropped>,
+    >,
     q_cursor_state: Query<&CursorState>,
+    q_throwable: Query<&Throwable>,
     q_droppable: Query<
         (
             Entity,
-            &Transform,
+            &GlobalTransform,
             Option<&Sprite>,
-            Option<&Handle<TextureAtlas>>,
+            Option<&Handle<ColorMaterial>>,
+            Option<(&TextureAtlasSprite, &Handle<TextureAtlas>)>,
+            &DropTarget,
         ),
         (
-            With<DropTarget>,
             Without<Dragged>,
             Or<(With<Sprite>, With<Handle<TextureAtlas>>)>,
         ),
     >,
 ) {
     let cursor_state = q_cursor_state.iter().next().unwrap();
+    let assets = SpriteAssets {
+        atlases: &*atlases,
+        materials: &*materials,
+    };
+
+    for (entity, Draggable(kind), transform, origin, proxy) in q_dropped.iter_mut() {
+        let candidates = q_droppable
+            .iter()
+            .filter(|(target, _, _, _, _, drop_target)| {
+                *target != entity && drop_target.will_accept(*kind)
+            })
+            .filter_map(|(target, global, sprite, material, atlas_sprite, _)| {
+                let hitbox = Hitbox::new(global, sprite, material, atlas_sprite, &assets)?;
+                Some((target, hitbox))
+            });
 
-    for entity in q_dropped.iter_mut() {
-        let mut dropped_onto = None;
+        let src = proxy.map_or(entity, |Proxy(spawner)| *spawner);
+        commands.remove_one::<Dropped>(entity);
 
-        for (entity, transform, sprite, t_atlas) in q_droppable.iter() {
-            let (x, y) = if let Some(sprite) = sprite {
-                (sprite.size.x, sprite.size.y)
-            } else if let Some(t_atlas) = t_atlas {
-                let atlas = atlas_assets.get(t_atlas).unwrap();
+        let dropped_onto = topmost(cursor_state.cursor_world, candidates, &*masks);
+        let velocity = cursor_state.cursor_velocity;
+        let thrown = q_throwable.get(src).is_ok() && velocity.length() >= TOSS_SPEED;
+        if thrown && dropped_onto.is_none() {
+            ev_tossed.send(TossedEvent { entity, velocity });
+            commands.insert_one(entity, Airborne);
+            continue;
+        }
 
-                println!("atlas: {:?}", atlas);
+        let target = dropped_onto.map(|dst| {
+            ev_dropped_onto.send(DroppedOntoEvent { src, dst });
 
-                (atlas.size.x, atlas.size.y)
-            } else {
-                unreachable!()
-            };
+            let (_, global, _, _, _, _) = q_droppable.get(dst).unwrap();
+            global
+        });
+        ev_dropped.send(DroppedEvent(src));
 
-            let half_width = x / 2.0;
-            let half_height = y / 2.0;
+        let settling = Settling::new(&*settings, transform, origin, target);
+        commands.insert_one(entity, settling);
+    }
+}
 
-            if transform.translation.x - half_width < cursor_state.cursor_world.x
-                && transform.translation.x + half_width > cursor_state.cursor_world.x
-                && transform.translation.y - half_height < cursor_state.cursor_world.y
-                && transform.translation.y + half_height > cursor_state.cursor_world.y
-            {
-                dropped_onto = Some(entity);
+/// thrown items that have come down are dropped where they landed, into the
+/// target they landed in if it takes them
+fn land(
+    commands: &mut Commands,
+    settings: Res<DragSettings>,
+    mut ev_dropped_onto: ResMut<Events<DroppedOntoEvent>>,
+    mut ev_dropped: ResMut<Events<DroppedEvent>>,
+    q_landed: Query<
+        (
+            Entity,
+            &Draggable,
+            &Transform,
+            &Landed,
+            Option<&Origin>,
+            Option<&Proxy>,
+        ),
+        Added<Landed>,
+    >,
+    q_target: Query<(&GlobalTransform, &DropTarget)>,
+) {
+    for (entity, Draggable(kind), transform, Landed(landed_in), origin, proxy) in q_landed.iter() {
+        let src = proxy.map_or(entity, |Proxy(spawner)| *spawner);
+        commands.remove_one::<Landed>(entity);
+        commands.remove_one::<Airborne>(entity);
+
+        let target = landed_in.and_then(|dst| {
+            let (global, target) = q_target.get(dst).ok()?;
+            if !target.will_accept(*kind) {
+                return None;
             }
+
+            ev_dropped_onto.send(DroppedOntoEvent { src, dst });
+            Some(global)
+        });
+        ev_dropped.send(DroppedEvent(src));
+
+        let settling = Settling::new(&*settings, transform, origin, target);
+        commands.insert_one(entity, settling);
+    }
+}
+
+impl Settling {
+    /// into `target` if there is one, otherwise back to where it came from
+    fn new(
+        settings: &DragSettings,
+        transform: &Transform,
+        origin: Option<&Origin>,
+        target: Option<&GlobalTransform>,
+    ) -> Self {
+        let from = transform.translation;
+        let (to, absorb) = match target {
+            Some(target) => (target.translation.truncate().extend(from.z), true),
+            None => (origin.map_or(from, |Origin(origin)| *origin), false),
+        };
+
+        let seconds = match (settings.animate_drops, absorb) {
+            (false, _) => 0.0,
+            (true, true) => ABSORB_TIME,
+            (true, false) => RETURN_TIME,
+        };
+
+        Settling {
+            from,
+            to,
+            scale: transform.scale,
+            timer: Timer::from_seconds(seconds, false),
+            absorb,
         }
+    }
+}
 
-        if let Some(dropped_onto) = dropped_onto {
-            ev_dropped_onto.send(DroppedOntoEvent {
-                src: entity,
-                dst: dropped_onto,
-            });
+/// moves dropped items home or into their target. copies and whatever was
+/// taken in are gone at the end
+fn settle(
+    commands: &mut Commands,
+    time: Res<Time>,
+    mut q_settling: Query<(Entity, &mut Settling, &mut Transform, Option<&Proxy>)>,
+) {
+    for (entity, mut settling, mut transform, proxy) in q_settling.iter_mut() {
+        settling.timer.tick(time.delta_seconds());
+
+        let t = if settling.timer.finished() {
+            1.0
+        } else {
+            settling.timer.percent()
+        };
+        transform.translation = settling.from.lerp(settling.to, t);
+        if settling.absorb {
+            transform.scale = settling.scale * (1.0 - (1.0 - ABSORB_SCALE) * t);
         }
-        ev_dropped.send(DroppedEvent(entity));
-        commands.remove_one::<Dropped>(entity);
+
+        if settling.timer.finished() {
+            if settling.absorb || proxy.is_some() {
+                commands.despawn_recursive(entity);
+            } else {
+                commands.remove_one::<Settling>(entity);
+            }
+        }
+    }
+}
+
+/// copies whose spawner went with its scene go too
+fn drop_orphans(
+    commands: &mut Commands,
+    q_proxy: Query<(Entity, &Proxy)>,
+    q_spawner: Query<&Spawner>,
+) {
+    for (entity, Proxy(spawner)) in q_proxy.iter() {
+        if q_spawner.get(*spawner).is_err() {
+            commands.despawn_recursive(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, z: f32) -> Hitbox {
+        let global = GlobalTransform::from_translation(Vec3::new(x, y, z));
+        Hitbox::at(&global, Vec2::new(20.0, 20.0), None, None)
+    }
+
+    /// asserts `hitbox` holds every point of `inside` and none of `outside`
+    fn check(hitbox: &Hitbox, inside: &[(f32, f32)], outside: &[(f32, f32)]) {
+        let masks = AlphaMasks::default();
+
+        for (x, y) in inside {
+            assert!(hitbox.contains(Vec2::new(*x, *y), &masks), "{} {}", x, y);
+        }
+        for (x, y) in outside {
+            assert!(!hitbox.contains(Vec2::new(*x, *y), &masks), "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    fn hitbox_rotated() {
+        let global = GlobalTransform {
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+            ..Default::default()
+        };
+        let hitbox = Hitbox::at(&global, Vec2::new(20.0, 20.0), None, None);
+
+        // a diamond reaching 14.1 along the axes
+        check(
+            &hitbox,
+            &[(13.5, 0.0), (0.0, -13.5), (6.5, 6.5)],
+            &[(14.5, 0.0), (0.0, -14.5), (8.0, 8.0)],
+        );
+    }
+
+    #[test]
+    fn hitbox_stretched() {
+        let global = GlobalTransform {
+            translation: Vec3::new(-50.0, 20.0, 3.0),
+            scale: Vec3::new(3.0, 0.5, 1.0),
+            ..Default::default()
+        };
+        let hitbox = Hitbox::at(&global, Vec2::new(20.0, 20.0), None, None);
+
+        check(
+            &hitbox,
+            &[(-21.0, 20.0), (-79.0, 20.0), (-50.0, 24.5), (-50.0, 15.5)],
+            &[(-19.0, 20.0), (-81.0, 20.0), (-50.0, 25.5), (-50.0, 14.5)],
+        );
+    }
+
+    #[test]
+    fn hitbox_of_a_child() {
+        let parent = GlobalTransform {
+            translation: Vec3::new(100.0, 50.0, 0.0),
+            scale: Vec3::splat(2.0),
+            ..Default::default()
+        };
+        let child = Transform {
+            translation: Vec3::new(10.0, 0.0, 1.0),
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            ..Default::default()
+        };
+        let global = parent.mul_transform(child);
+        let hitbox = Hitbox::at(&global, Vec2::new(20.0, 10.0), None, None);
+
+        // centred on (120, 50), turned on its side and doubled
+        assert!((hitbox.depth - 2.0).abs() < 1e-4);
+        check(
+            &hitbox,
+            &[(120.0, 50.0), (110.5, 50.0), (129.5, 50.0), (120.0, 69.0)],
+            &[(109.5, 50.0), (130.5, 50.0), (120.0, 71.0), (120.0, 29.0)],
+        );
+    }
+
+    #[test]
+    fn topmost_picks_the_higher_z() {
+        let masks = AlphaMasks::default();
+        let (low, high) = (Entity::new(1), Entity::new(2));
+        let point = Vec2::new(5.0, 5.0);
+
+        let picked = topmost(
+            point,
+            vec![(low, square(0.0, 0.0, 1.0)), (high, square(8.0, 8.0, 2.0))],
+            &masks,
+        );
+        assert_eq!(picked, Some(high));
+
+        let picked = topmost(
+            point,
+            vec![(high, square(8.0, 8.0, 2.0)), (low, square(0.0, 0.0, 1.0))],
+            &masks,
+        );
+        assert_eq!(picked, Some(high));
+    }
+
+    #[test]
+    fn topmost_breaks_ties_the_same_way_in_any_order() {
+        let masks = AlphaMasks::default();
+        let (a, b) = (Entity::new(3), Entity::new(7));
+        let point = Vec2::zero();
+
+        let forwards = topmost(
+            point,
+            vec![(a, square(0.0, 0.0, 5.0)), (b, square(1.0, 0.0, 5.0))],
+            &masks,
+        );
+        let backwards = topmost(
+            point,
+            vec![(b, square(1.0, 0.0, 5.0)), (a, square(0.0, 0.0, 5.0))],
+            &masks,
+        );
+        assert_eq!(forwards, Some(b));
+        assert_eq!(backwards, Some(b));
+    }
+
+    #[test]
+    fn topmost_misses() {
+        let masks = AlphaMasks::default();
+        let candidates = vec![
+            (Entity::new(1), square(0.0, 0.0, 1.0)),
+            (Entity::new(2), square(30.0, 0.0, 2.0)),
+        ];
+
+        assert_eq!(topmost(Vec2::new(15.0, 0.0), candidates, &masks), None);
+        assert_eq!(topmost(Vec2::zero(), vec![], &masks), None);
     }
 }
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use crate::brewing::Brew;
+use crate::cauldron_scene::CauldronContents;
+use crate::gamestate::{GameStage, GameState};
+use crate::jam::{self, JamAssets, JamEffect};
+use crate::recipes::RecipeBook;
+use crate::settings::Settings;
+use crate::stations::ActiveStation;
+
+pub struct EffectsPreviewPlugin;
+
+impl Plugin for EffectsPreviewPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.on_state_update(GameStage::Main, GameState::Cauldron, show_preview.system())
+            .on_state_exit(GameStage::Main, GameState::Cauldron, teardown.system());
+    }
+}
+
+/// how opaque an effect that's one ingredient short is drawn
+const ALMOST_ALPHA: f32 = 0.35;
+
+/// the strip of effect icons along the top of the cauldron scene
+struct PreviewStrip;
+
+fn teardown(commands: &mut Commands, q_strip: Query<Entity, With<PreviewStrip>>) {
+    for entity in q_strip.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+fn show_preview(
+    commands: &mut Commands,
+    // what the strip is showing, `true` for the effects that are almost there
+    mut shown: Local<Vec<(JamEffect, bool)>>,
+    settings: Res<Settings>,
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &Brew)>,
+    recipes: Res<RecipeBook>,
+    jam_assets: Res<JamAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_strip: Query<Entity, With<PreviewStrip>>,
+) {
+    let preview = if settings.preview_effects {
+        let (contents, brew) = q_station.get(active.0).unwrap();
+        let strengths = brew.strengths(contents, &*recipes);
+
+        JamEffect::all()
+            .iter()
+            .filter_map(|effect| match strengths.get(effect) {
+                Some(s) if *s >= jam::EFFECT_THRESHOLD => Some((*effect, false)),
+                Some(s) if *s + 1 == jam::EFFECT_THRESHOLD => Some((*effect, true)),
+                _ => None,
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    if *shown == preview && q_strip.iter().next().is_some() {
+        return;
+    }
+
+    for entity in q_strip.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                position: Rect {
+                    top: Val::Px(20.0),
+                    left: Val::Px(300.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .with(PreviewStrip)
+        .with_children(|parent| {
+            for (effect, almost) in &preview {
+                let mut material: ColorMaterial = effect.asset_for(&*jam_assets).into();
+                if *almost {
+                    material.color.set_a(ALMOST_ALPHA);
+                }
+
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(32.0), Val::Px(32.0)),
+                        margin: Rect::all(Val::Px(2.0)),
+                        ..Default::default()
+                    },
+                    material: materials.add(material),
+                    ..Default::default()
+                });
+            }
+        });
+
+    *shown = preview;
+}
@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::brewing::{Brew, BrewState};
+use crate::button;
+use crate::cauldron_scene::{CauldronCapacity, CauldronContents};
+use crate::gamestate::{GameStage, GameState};
+use crate::jam::{JamAssets, JamIngredient};
+use crate::stations::ActiveStation;
+
+pub struct IngredientHistoryPlugin;
+
+impl Plugin for IngredientHistoryPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Histories::default())
+            .insert_resource(PanelState::default())
+            .add_system(track_contents.system())
+            .on_state_enter(GameStage::Main, GameState::Cauldron, setup.system())
+            // clicks go first so the panel sees the change in the same frame
+            .on_state_update(GameStage::Main, GameState::Cauldron, handle_click.system())
+            .on_state_update(GameStage::Main, GameState::Cauldron, mark_dirty.system())
+            .on_state_update(GameStage::Main, GameState::Cauldron, show_history.system())
+            .on_state_exit(GameStage::Main, GameState::Cauldron, teardown.system());
+    }
+}
+
+/// the side panel listing what's gone into the cauldron
+struct HistoryPanel;
+struct UndoButton;
+struct RedoButton;
+/// takes out the ingredient at this index
+struct RemoveButton(usize);
+
+/// something done to a cauldron's contents, with where it happened
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Added(usize, JamIngredient),
+    Removed(usize, JamIngredient),
+}
+
+impl Action {
+    fn inverse(self) -> Action {
+        match self {
+            Action::Added(idx, ingredient) => Action::Removed(idx, ingredient),
+            Action::Removed(idx, ingredient) => Action::Added(idx, ingredient),
+        }
+    }
+
+    /// whether it can be done to `contents` as they are now
+    fn allowed(
+        &self,
+        contents: &CauldronContents,
+        capacity: &CauldronCapacity,
+        brew: &Brew,
+    ) -> bool {
+        match self {
+            Action::Added(idx, _) => contents.0.len() < capacity.size && *idx <= contents.0.len(),
+            Action::Removed(idx, ingredient) => {
+                can_remove(brew, contents) && contents.0.get(*idx) == Some(ingredient)
+            }
+        }
+    }
+
+    fn apply(&self, contents: &mut CauldronContents, brew: &mut Brew) {
+        match *self {
+            Action::Added(idx, ingredient) => {
+                contents.0.insert(idx, ingredient);
+                brew.add_ingredient(contents.0.len());
+            }
+            Action::Removed(idx, _) => {
+                contents.0.remove(idx);
+            }
+        }
+    }
+}
+
+/// what's been done to a station's cauldron, so undo and redo replay the
+/// player's actions in the order they happened
+#[derive(Default)]
+struct History {
+    /// most recent last
+    done: Vec<Action>,
+    /// the last undone is put back first
+    undone: Vec<Action>,
+    /// the contents as the history last left them, a change that isn't an
+    /// ingredient going in means it no longer makes sense
+    seen: Vec<JamIngredient>,
+}
+
+impl History {
+    /// keeps up with changes made outside the panel
+    fn notice(&mut self, contents: &CauldronContents) {
+        if contents.0 == self.seen {
+            return;
+        }
+
+        match contents.0.split_last() {
+            Some((ingredient, before)) if before == &self.seen[..] => {
+                self.done.push(Action::Added(before.len(), *ingredient));
+                self.undone.clear();
+            }
+            _ => {
+                self.done.clear();
+                self.undone.clear();
+            }
+        }
+        self.seen = contents.0.clone();
+    }
+
+    fn can_undo(
+        &self,
+        contents: &CauldronContents,
+        capacity: &CauldronCapacity,
+        brew: &Brew,
+    ) -> bool {
+        self.done
+            .last()
+            .map_or(false, |a| a.inverse().allowed(contents, capacity, brew))
+    }
+
+    fn can_redo(
+        &self,
+        contents: &CauldronContents,
+        capacity: &CauldronCapacity,
+        brew: &Brew,
+    ) -> bool {
+        self.undone
+            .last()
+            .map_or(false, |a| a.allowed(contents, capacity, brew))
+    }
+
+    fn undo(
+        &mut self,
+        contents: &mut CauldronContents,
+        capacity: &CauldronCapacity,
+        brew: &mut Brew,
+    ) {
+        if !self.can_undo(contents, capacity, brew) {
+            return;
+        }
+
+        let action = self.done.pop().unwrap();
+        action.inverse().apply(contents, brew);
+        self.undone.push(action);
+        self.seen = contents.0.clone();
+    }
+
+    fn redo(
+        &mut self,
+        contents: &mut CauldronContents,
+        capacity: &CauldronCapacity,
+        brew: &mut Brew,
+    ) {
+        if !self.can_redo(contents, capacity, brew) {
+            return;
+        }
+
+        let action = self.undone.pop().unwrap();
+        action.apply(contents, brew);
+        self.done.push(action);
+        self.seen = contents.0.clone();
+    }
+
+    /// takes out the ingredient at `idx`, a new action so nothing's left to redo
+    fn remove(
+        &mut self,
+        idx: usize,
+        contents: &mut CauldronContents,
+        capacity: &CauldronCapacity,
+        brew: &mut Brew,
+    ) {
+        let action = match contents.0.get(idx) {
+            Some(ingredient) => Action::Removed(idx, *ingredient),
+            None => return,
+        };
+        if !action.allowed(contents, capacity, brew) {
+            return;
+        }
+
+        action.apply(contents, brew);
+        self.done.push(action);
+        self.undone.clear();
+        self.seen = contents.0.clone();
+    }
+}
+
+/// each station's history, by its entity
+#[derive(Default)]
+struct Histories(HashMap<Entity, History>);
+
+/// once jam has set (or burnt) there's no fishing things out of it
+fn can_remove(brew: &Brew, contents: &CauldronContents) -> bool {
+    brew.state(contents) == BrewState::UnderCooked
+}
+
+#[derive(Default)]
+struct PanelState {
+    /// whether the panel was last built with remove, undo and redo buttons
+    buttons: (bool, bool, bool),
+    /// the station the panel was last built for
+    station: Option<Entity>,
+    dirty: bool,
+}
+
+fn setup(mut panel_state: ResMut<PanelState>) {
+    panel_state.dirty = true;
+}
+
+fn teardown(commands: &mut Commands, q_panel: Query<Entity, With<HistoryPanel>>) {
+    for entity in q_panel.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+fn track_contents(
+    mut histories: ResMut<Histories>,
+    q_changed: Query<(Entity, &CauldronContents), Changed<CauldronContents>>,
+) {
+    for (entity, contents) in q_changed.iter() {
+        histories.0.entry(entity).or_default().notice(contents);
+    }
+}
+
+fn mark_dirty(
+    active: Res<ActiveStation>,
+    mut panel_state: ResMut<PanelState>,
+    q_changed: Query<&CauldronContents, Changed<CauldronContents>>,
+) {
+    let switched = panel_state.station != Some(active.0);
+    if !switched && q_changed.get(active.0).is_err() {
+        return;
+    }
+
+    panel_state.station = Some(active.0);
+    panel_state.dirty = true;
+}
+
+/// rebuilds the panel whenever the contents change, or the buttons that can be
+/// used do, like when it becomes too late to take things out
+fn show_history(
+    commands: &mut Commands,
+    mut panel_state: ResMut<PanelState>,
+    histories: Res<Histories>,
+    active: Res<ActiveStation>,
+    q_station: Query<(&CauldronContents, &CauldronCapacity, &Brew)>,
+    asset_server: Res<AssetServer>,
+    jam_assets: Res<JamAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_panel: Query<Entity, With<HistoryPanel>>,
+) {
+    let (contents, capacity, brew) = q_station.get(active.0).unwrap();
+    let removable = can_remove(brew, contents);
+    let (undoable, redoable) = match histories.0.get(&active.0) {
+        Some(history) => (
+            history.can_undo(contents, capacity, brew),
+            history.can_redo(contents, capacity, brew),
+        ),
+        None => (false, false),
+    };
+
+    let buttons = (removable, undoable, redoable);
+    if !panel_state.dirty && buttons == panel_state.buttons {
+        return;
+    }
+    panel_state.dirty = false;
+    panel_state.buttons = buttons;
+
+    for entity in q_panel.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 14.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(6.0)),
+                position: Rect {
+                    top: Val::Px(60.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            ..Default::default()
+        })
+        .with(HistoryPanel)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::with_section("Added", text_style.clone(), Default::default()),
+                ..Default::default()
+            });
+
+            for (idx, ingredient) in contents.0.iter().enumerate() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: materials.add(Color::NONE.into()),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(16.0), Val::Px(16.0)),
+                                margin: Rect::all(Val::Px(2.0)),
+                                ..Default::default()
+                            },
+                            material: materials.add(ingredient.asset_for(&*jam_assets).into()),
+                            ..Default::default()
+                        });
+
+                        parent.spawn(TextBundle {
+                            text: Text::with_section(
+                                ingredient.name(),
+                                text_style.clone(),
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+
+                        if removable {
+                            button::spawn_text_button(
+                                parent,
+                                &mut *materials,
+                                font.clone(),
+                                "x",
+                                10.0,
+                            )
+                            .with(RemoveButton(idx));
+                        }
+                    });
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::NONE.into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    if undoable {
+                        button::spawn_text_button(
+                            parent,
+                            &mut *materials,
+                            font.clone(),
+                            "Undo",
+                            12.0,
+                        )
+                        .with(UndoButton);
+                    }
+
+                    if redoable {
+                        button::spawn_text_button(
+                            parent,
+                            &mut *materials,
+                            font.clone(),
+                            "Redo",
+                            12.0,
+                        )
+                        .with(RedoButton);
+                    }
+                });
+        });
+}
+
+fn handle_click(
+    mut histories: ResMut<Histories>,
+    active: Res<ActiveStation>,
+    mut q_station: Query<(&mut CauldronContents, &CauldronCapacity, &mut Brew)>,
+    q_undo: Query<&UndoButton>,
+    q_redo: Query<&RedoButton>,
+    q_remove: Query<&RemoveButton>,
+    mut event_reader: EventReader<button::ButtonPressedEvent>,
+) {
+    for button::ButtonPressedEvent(entity) in event_reader.iter() {
+        let (mut contents, capacity, mut brew) = q_station.get_mut(active.0).unwrap();
+        let history = histories.0.entry(active.0).or_default();
+        history.notice(&*contents);
+
+        if let Ok(RemoveButton(idx)) = q_remove.get_component(*entity) {
+            history.remove(*idx, &mut *contents, capacity, &mut *brew);
+        } else if let Ok(UndoButton) = q_undo.get_component(*entity) {
+            history.undo(&mut *contents, capacity, &mut *brew);
+        } else if let Ok(RedoButton) = q_redo.get_component(*entity) {
+            history.redo(&mut *contents, capacity, &mut *brew);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jam::JamIngredient::{Lemons, Salt, Sugar};
+
+    /// a cauldron as the player left it
+    struct Station {
+        history: History,
+        contents: CauldronContents,
+        capacity: CauldronCapacity,
+        brew: Brew,
+    }
+
+    impl Station {
+        fn new() -> Self {
+            Station {
+                history: History::default(),
+                contents: CauldronContents(vec![]),
+                capacity: CauldronCapacity::default(),
+                brew: Brew::default(),
+            }
+        }
+
+        fn add(&mut self, ingredient: JamIngredient) {
+            self.contents.0.push(ingredient);
+            self.history.notice(&self.contents);
+        }
+
+        fn remove(&mut self, idx: usize) {
+            self.history
+                .remove(idx, &mut self.contents, &self.capacity, &mut self.brew);
+        }
+
+        fn undo(&mut self) {
+            self.history
+                .undo(&mut self.contents, &self.capacity, &mut self.brew);
+        }
+
+        fn redo(&mut self) {
+            self.history
+                .redo(&mut self.contents, &self.capacity, &mut self.brew);
+        }
+
+        fn can_undo(&self) -> bool {
+            self.history
+                .can_undo(&self.contents, &self.capacity, &self.brew)
+        }
+
+        fn can_redo(&self) -> bool {
+            self.history
+                .can_redo(&self.contents, &self.capacity, &self.brew)
+        }
+    }
+
+    #[test]
+    fn undo_follows_the_order_things_were_done() {
+        let mut station = Station::new();
+        station.add(Sugar);
+        station.add(Lemons);
+        station.add(Salt);
+
+        station.remove(0);
+        assert_eq!(station.contents.0, vec![Lemons, Salt]);
+
+        // the sugar taken out goes back first, then the salt comes out
+        station.undo();
+        assert_eq!(station.contents.0, vec![Sugar, Lemons, Salt]);
+        station.undo();
+        assert_eq!(station.contents.0, vec![Sugar, Lemons]);
+
+        station.redo();
+        assert_eq!(station.contents.0, vec![Sugar, Lemons, Salt]);
+        station.undo();
+        assert_eq!(station.contents.0, vec![Sugar, Lemons]);
+
+        station.redo();
+        station.redo();
+        assert_eq!(station.contents.0, vec![Lemons, Salt]);
+        assert!(!station.can_redo());
+    }
+
+    #[test]
+    fn doing_something_new_clears_redo() {
+        let mut station = Station::new();
+        station.add(Sugar);
+        station.add(Lemons);
+
+        station.undo();
+        assert!(station.can_redo());
+
+        station.add(Salt);
+        assert!(!station.can_redo());
+        station.undo();
+        assert_eq!(station.contents.0, vec![Sugar]);
+    }
+
+    #[test]
+    fn other_changes_forget_the_history() {
+        let mut station = Station::new();
+        station.add(Sugar);
+        station.add(Lemons);
+
+        // bottled
+        station.contents.0.clear();
+        station.history.notice(&station.contents);
+        assert!(!station.can_undo());
+
+        station.add(Salt);
+        station.undo();
+        assert!(station.contents.0.is_empty());
+        assert!(!station.can_undo());
+    }
+
+    #[test]
+    fn set_jam_keeps_what_is_in_it() {
+        let mut station = Station::new();
+        station.add(Sugar);
+        station.brew.cooked = 1.0;
+
+        assert!(!station.can_undo());
+        station.remove(0);
+        station.undo();
+        assert_eq!(station.contents.0, vec![Sugar]);
+    }
+}
@@ -1,9 +1,12 @@
 use std::collections::{HashMap, HashSet};
 
 use bevy::prelude::*;
+use serde::Deserialize;
 
 use crate::dragging;
 use crate::gamestate::{GameStage, GameState};
+use crate::settings::Palette;
+use crate::utils;
 
 pub struct JamPlugin;
 
@@ -11,16 +14,6 @@ impl Plugin for JamPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_startup_system_to_stage(StartupStage::PreStartup, setup_assets.system())
             .on_state_enter(GameStage::Main, GameState::Cauldron, setup.system())
-            .on_state_update(
-                GameStage::Main,
-                GameState::Cauldron,
-                jam_clone_on_drag.system(),
-            )
-            .on_state_update(
-                GameStage::Main,
-                GameState::Cauldron,
-                jam_remove_on_drop.system(),
-            )
             .on_state_exit(GameStage::Main, GameState::Cauldron, teardown.system());
     }
 }
@@ -72,33 +65,9 @@ fn spawn_ingredient(
         })
         .with(ingredient)
         .with(dragging::Hoverable)
-        .with(dragging::Draggable);
-}
-
-fn jam_clone_on_drag(
-    commands: &mut Commands,
-    jam_assets: Res<JamAssets>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    q_ingredients: Query<&JamIngredient>,
-    mut event_reader: EventReader<dragging::DraggedEvent>,
-) {
-    for dragging::DraggedEvent(entity) in event_reader.iter() {
-        if let Ok(ingredient) = q_ingredients.get_component(*entity) {
-            spawn_ingredient(commands, *ingredient, &*jam_assets, &mut *materials);
-        }
-    }
-}
-
-fn jam_remove_on_drop(
-    commands: &mut Commands,
-    q_jam_ingredient: Query<&JamIngredient>,
-    mut event_reader: EventReader<dragging::DroppedEvent>,
-) {
-    for dragging::DroppedEvent(entity) in event_reader.iter() {
-        if let Ok(_) = q_jam_ingredient.get_component::<JamIngredient>(*entity) {
-            commands.despawn(*entity);
-        }
-    }
+        .with(dragging::Draggable(dragging::DragKind::Ingredient))
+        .with(dragging::Spawner)
+        .with(dragging::Throwable);
 }
 
 pub struct JamAssets {
@@ -106,7 +75,7 @@ pub struct JamAssets {
     effects: HashMap<JamEffect, Handle<Texture>>,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize)]
 pub enum JamIngredient {
     Petrol,
     Urine,
@@ -170,7 +139,25 @@ impl JamIngredient {
         }
     }
 
-    pub fn colour(self) -> Color {
+    /// the colour of the ingredient in a palette, everything that draws an
+    /// ingredient's colour goes through this
+    pub fn colour(self, palette: Palette) -> Color {
+        let idx = JamIngredient::all()
+            .iter()
+            .position(|i| *i == self)
+            .unwrap();
+        // a 4x4 grid of lightness against the axis the palette can tell apart
+        let lightness = 0.4 + 0.16 * (idx / 4) as f32;
+        let axis = -0.12 + 0.08 * (idx % 4) as f32;
+
+        match palette {
+            Palette::Standard => self.standard_colour(),
+            Palette::RedGreen => utils::from_oklab([lightness, 0.0, axis]),
+            Palette::BlueYellow => utils::from_oklab([lightness, axis, 0.0]),
+        }
+    }
+
+    fn standard_colour(self) -> Color {
         match self {
             JamIngredient::Petrol => Color::rgb_u8(237, 237, 84),
             JamIngredient::Urine => Color::rgb_u8(255, 172, 0),
@@ -191,6 +178,19 @@ impl JamIngredient {
         }
     }
 
+    /// how much an ingredient colours the jam compared to the others, strong
+    /// dyes like damsons and oil take over a mix, watery things barely show
+    pub fn colour_strength(self) -> f32 {
+        match self {
+            JamIngredient::Damsons | JamIngredient::MotorOil => 2.0,
+            JamIngredient::Strawberries | JamIngredient::Absinth => 1.5,
+            JamIngredient::HumanFlesh | JamIngredient::AppleSeeds => 1.25,
+            JamIngredient::Sand | JamIngredient::GunPowder => 0.75,
+            JamIngredient::BathWater | JamIngredient::Sugar | JamIngredient::Salt => 0.4,
+            _ => 1.0,
+        }
+    }
+
     fn initial_transform(self) -> Transform {
         let (x, y) = self.initial_position();
         Transform::from_xyz(x, y, 6.0)
@@ -263,21 +263,31 @@ impl JamIngredient {
         }
     }
 
-    pub fn calculate_effects(ingredients: &[Self]) -> HashSet<JamEffect> {
+    /// how many of the ingredients have each effect
+    pub fn effect_strengths(ingredients: &[Self]) -> HashMap<JamEffect, u32> {
         let mut seen = HashMap::new();
 
         for effect in ingredients.iter().flat_map(|i| i.effects()) {
-            *seen.entry(effect).or_insert(0) += 1;
+            *seen.entry(*effect).or_insert(0) += 1;
         }
 
-        seen.into_iter()
-            .filter(|(_k, v)| *v > 1)
-            .map(|(k, _)| *k)
-            .collect()
+        seen
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// how strong an effect has to be for the jam to have it, two ingredients
+/// sharing it
+pub const EFFECT_THRESHOLD: u32 = 2;
+
+pub fn active_effects(strengths: &HashMap<JamEffect, u32>) -> HashSet<JamEffect> {
+    strengths
+        .iter()
+        .filter(|(_k, v)| **v >= EFFECT_THRESHOLD)
+        .map(|(k, _)| *k)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Deserialize)]
 pub enum JamEffect {
     NightVision,
     SuperHumanStrength,
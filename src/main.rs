@@ -1,20 +1,39 @@
 use bevy::prelude::*;
 
+mod ageing;
+mod alpha_mask;
+mod brewing;
 mod button;
 mod cauldron_scene;
+mod deliveries;
+mod dialogue;
 mod dragging;
+mod effects_preview;
 mod gamestate;
+mod ingredient_history;
 mod jam;
 mod pop_ups;
+mod quests;
+mod recipes;
+mod reports;
+mod settings;
+mod shelf;
 mod shop_scene;
+mod speech_bubble;
+mod stations;
+mod stirring;
+mod toss;
 mod utils;
+mod viewport;
+mod virtual_cursor;
 
 fn main() {
     App::build()
         .insert_resource(WindowDescriptor {
             title: "Game Jam Jam Game".to_string(),
-            width: 800.0,
-            height: 600.0,
+            width: viewport::WIDTH,
+            height: viewport::HEIGHT,
+            resizable: true,
             vsync: true,
             ..Default::default()
         })
@@ -25,12 +44,30 @@ fn main() {
         )
         .insert_resource(State::new(gamestate::GameState::Main))
         .add_plugins(DefaultPlugins)
+        .add_plugin(viewport::ViewportPlugin)
+        .add_plugin(settings::SettingsPlugin)
         .add_plugin(button::ButtonPlugin)
+        .add_plugin(alpha_mask::AlphaMaskPlugin)
         .add_plugin(dragging::DragPlugin)
+        .add_plugin(toss::TossPlugin)
+        .add_plugin(virtual_cursor::VirtualCursorPlugin)
         .add_plugin(shop_scene::ShopScenePlugin)
+        .add_plugin(shelf::ShelfPlugin)
+        .add_plugin(ageing::AgeingPlugin)
         .add_plugin(jam::JamPlugin)
         .add_plugin(pop_ups::PopUpsPlugin)
         .add_plugin(cauldron_scene::CauldronScenePlugin)
+        .add_plugin(stations::StationsPlugin)
+        .add_plugin(brewing::BrewingPlugin)
+        .add_plugin(stirring::StirringPlugin)
+        .add_plugin(recipes::RecipesPlugin)
+        .add_plugin(ingredient_history::IngredientHistoryPlugin)
+        .add_plugin(effects_preview::EffectsPreviewPlugin)
+        .add_plugin(deliveries::DeliveriesPlugin)
+        .add_plugin(quests::QuestsPlugin)
+        .add_plugin(reports::ReportsPlugin)
+        .add_plugin(dialogue::DialoguePlugin)
+        .add_plugin(speech_bubble::SpeechBubblePlugin)
         .add_startup_system(setup_ui.system())
         .run();
 }
@@ -42,5 +79,6 @@ fn setup_ui(
 ) {
     commands
         .spawn(UiCameraBundle::default())
-        .spawn(OrthographicCameraBundle::new_2d());
+        .spawn(OrthographicCameraBundle::new_2d())
+        .with(viewport::WorldCamera);
 }
@@ -1,6 +1,7 @@
 use bevy::{prelude::*, ui::FocusPolicy};
 
 use crate::jam::JamIngredient;
+use crate::recipes::RecipeBook;
 use crate::shop_scene;
 use crate::{button, jam::JamAssets};
 use crate::{
@@ -95,6 +96,7 @@ fn spawn_jam_book(
     materials: &mut Assets<ColorMaterial>,
     asset_server: &AssetServer,
     jam_assets: &JamAssets,
+    recipes: &RecipeBook,
 ) {
     let recipe_book_handle = asset_server.load("sprites/recipebook.png");
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
@@ -268,6 +270,37 @@ fn spawn_jam_book(
                                         });
                                     });
                             }
+
+                            for (name, description) in recipes.discovered() {
+                                parent.spawn(TextBundle {
+                                    style: Style {
+                                        max_size: Size::new(Val::Px(240.0), Val::Undefined),
+                                        ..Default::default()
+                                    },
+                                    text: Text {
+                                        sections: vec![
+                                            TextSection {
+                                                value: name.to_string(),
+                                                style: TextStyle {
+                                                    font: font.clone(),
+                                                    font_size: 14.0,
+                                                    color: Color::BLACK,
+                                                },
+                                            },
+                                            TextSection {
+                                                value: format!("\n{}", description),
+                                                style: TextStyle {
+                                                    font: font.clone(),
+                                                    font_size: 11.0,
+                                                    color: Color::BLACK,
+                                                },
+                                            },
+                                        ],
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                });
+                            }
                         });
                 });
         });
@@ -290,6 +323,7 @@ fn handle_jam_book_click(
     mut materials: ResMut<Assets<ColorMaterial>>,
     asset_server: Res<AssetServer>,
     jam_assets: Res<JamAssets>,
+    recipes: Res<RecipeBook>,
     q_jambook_button: Query<&JamBookButton>,
     q_jambook: Query<Entity, With<JamBook>>,
     mut event_reader: EventReader<button::ButtonPressedEvent>,
@@ -301,7 +335,13 @@ fn handle_jam_book_click(
         }
 
         if let Ok(JamBookButton) = q_jambook_button.get_component(*entity) {
-            spawn_jam_book(commands, &mut *materials, &*asset_server, &*jam_assets);
+            spawn_jam_book(
+                commands,
+                &mut *materials,
+                &*asset_server,
+                &*jam_assets,
+                &*recipes,
+            );
         }
     }
 }
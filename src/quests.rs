@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::deliveries::{CustomerLeftEvent, Delivery, DeliveryHistory};
+use crate::jam::JamEffect;
+use crate::utils::load_data;
+
+pub struct QuestsPlugin;
+
+impl Plugin for QuestsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, setup.system())
+            .add_system(advance_quests.system());
+    }
+}
+
+fn setup(commands: &mut Commands) {
+    let arcs: Vec<QuestArc> = load_data("quests.ron");
+
+    for arc in &arcs {
+        arc.validate();
+    }
+
+    commands.insert_resource(QuestLog::new(arcs));
+}
+
+/// a story told over several visits by the same customer, which step comes
+/// next depends on the jams they were given before
+#[derive(Deserialize, Debug)]
+pub struct QuestArc {
+    pub name: String,
+    pub customer: CustomerLook,
+    /// how many customers visit before this arc starts
+    #[serde(default)]
+    pub starts_after: u32,
+    pub start: String,
+    pub steps: HashMap<String, QuestStep>,
+}
+
+impl QuestArc {
+    fn validate(&self) {
+        let names = std::iter::once(&self.start).chain(
+            self.steps
+                .values()
+                .flat_map(|s| s.branches.iter().map(|b| &b.next)),
+        );
+
+        for name in names {
+            if !self.steps.contains_key(name) {
+                panic!("quest arc '{}' has no step named '{}'", self.name, name);
+            }
+        }
+    }
+}
+
+/// sprite sheet indexes, so returning customers are recognisable
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CustomerLook {
+    pub hair: u32,
+    pub face: u32,
+    pub torso: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QuestStep {
+    pub story: String,
+    pub requirements: Vec<JamEffect>,
+    /// the first branch whose condition holds is taken, no branches (or none
+    /// holding) ends the arc
+    #[serde(default)]
+    pub branches: Vec<QuestBranch>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QuestBranch {
+    pub when: Condition,
+    pub next: String,
+    /// how many other customers visit before the next step
+    #[serde(default)]
+    pub after: u32,
+}
+
+/// checked against the deliveries made so far in the same arc
+#[derive(Deserialize, Debug)]
+pub enum Condition {
+    Always,
+    /// the last jam given in this arc had every required effect
+    Met,
+    /// the last visit in this arc didn't get a jam with every required effect
+    Failed,
+    /// the last visit in this arc was given a jam, whatever it did
+    Served,
+    /// the last jam given in this arc had this effect
+    Delivered(JamEffect),
+    /// any jam given in this arc had this effect
+    EverDelivered(JamEffect),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn holds(&self, history: &[&Delivery]) -> bool {
+        let last = history.last();
+
+        match self {
+            Condition::Always => true,
+            Condition::Met => last.map_or(false, |d| d.met()),
+            Condition::Failed => !last.map_or(false, |d| d.met()),
+            Condition::Served => last.map_or(false, |d| d.delivered.is_some()),
+            Condition::Delivered(effect) => last.map_or(false, |d| d.has_effect(*effect)),
+            Condition::EverDelivered(effect) => history.iter().any(|d| d.has_effect(*effect)),
+            Condition::Not(c) => !c.holds(history),
+            Condition::All(cs) => cs.iter().all(|c| c.holds(history)),
+            Condition::Any(cs) => cs.iter().any(|c| c.holds(history)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestStepId {
+    pub arc: usize,
+    pub step: String,
+}
+
+struct Scheduled {
+    step: QuestStepId,
+    /// the visit number this step can happen on
+    due: u32,
+}
+
+pub struct QuestVisit<'a> {
+    pub id: QuestStepId,
+    pub step: &'a QuestStep,
+    pub customer: CustomerLook,
+}
+
+pub struct QuestLog {
+    arcs: Vec<QuestArc>,
+    visits: u32,
+    scheduled: Vec<Scheduled>,
+}
+
+impl QuestLog {
+    fn new(arcs: Vec<QuestArc>) -> Self {
+        let scheduled = arcs
+            .iter()
+            .enumerate()
+            .map(|(arc, a)| Scheduled {
+                step: QuestStepId {
+                    arc,
+                    step: a.start.clone(),
+                },
+                due: a.starts_after,
+            })
+            .collect();
+
+        QuestLog {
+            arcs,
+            visits: 0,
+            scheduled,
+        }
+    }
+
+    /// called for every new customer, gives the quest step they're here for
+    /// if there is one due
+    pub fn next_visit(&mut self) -> Option<QuestVisit> {
+        let visits = self.visits;
+        self.visits += 1;
+
+        let idx = self.scheduled.iter().position(|s| s.due <= visits)?;
+        let Scheduled { step: id, .. } = self.scheduled.remove(idx);
+        let arc = &self.arcs[id.arc];
+
+        Some(QuestVisit {
+            step: &arc.steps[&id.step],
+            customer: arc.customer,
+            id,
+        })
+    }
+
+    pub fn has_arc(&self, name: &str) -> bool {
+        self.arcs.iter().any(|a| a.name == name)
+    }
+
+    /// how a quest arc's customer feels about the shop, up one for every jam
+    /// that did what they needed and down one for every visit that didn't.
+    /// `None` if there's no arc by that name
+    pub fn standing(&self, name: &str, history: &DeliveryHistory) -> Option<i32> {
+        let arc = self.arcs.iter().position(|a| a.name == name)?;
+
+        Some(
+            history
+                .for_arc(arc)
+                .map(|d| if d.met() { 1 } else { -1 })
+                .sum(),
+        )
+    }
+
+    fn conclude(&mut self, id: &QuestStepId, history: &DeliveryHistory) {
+        let step = &self.arcs[id.arc].steps[&id.step];
+        let arc_history: Vec<&Delivery> = history.for_arc(id.arc).collect();
+
+        let next = step
+            .branches
+            .iter()
+            .find(|b| b.when.holds(&arc_history))
+            .map(|branch| Scheduled {
+                step: QuestStepId {
+                    arc: id.arc,
+                    step: branch.next.clone(),
+                },
+                due: self.visits + branch.after,
+            });
+
+        if let Some(next) = next {
+            self.scheduled.push(next);
+        }
+    }
+}
+
+fn advance_quests(
+    mut quest_log: ResMut<QuestLog>,
+    history: Res<DeliveryHistory>,
+    mut event_reader: EventReader<CustomerLeftEvent>,
+) {
+    for CustomerLeftEvent(delivery) in event_reader.iter() {
+        if let Some(id) = &delivery.quest {
+            quest_log.conclude(id, &*history);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::jam::JamEffect::{Flight, Hunger, Speed};
+
+    fn effects(effects: &[JamEffect]) -> HashSet<JamEffect> {
+        effects.iter().copied().collect()
+    }
+
+    /// a visit that needed `requirements` and got a jam with `delivered`, if
+    /// it got one at all
+    fn visit(requirements: &[JamEffect], delivered: Option<&[JamEffect]>) -> Delivery {
+        Delivery {
+            quest: None,
+            requirements: effects(requirements),
+            delivered: delivered.map(effects),
+            spoiled: false,
+        }
+    }
+
+    #[test]
+    fn met_and_failed_look_at_the_last_visit() {
+        let met = visit(&[Speed], Some(&[Speed, Hunger]));
+        let unmet = visit(&[Speed], Some(&[Hunger]));
+
+        assert!(Condition::Met.holds(&[&unmet, &met]));
+        assert!(!Condition::Failed.holds(&[&unmet, &met]));
+        assert!(!Condition::Met.holds(&[&met, &unmet]));
+        assert!(Condition::Failed.holds(&[&met, &unmet]));
+
+        assert!(!Condition::Met.holds(&[]));
+        assert!(Condition::Failed.holds(&[]));
+    }
+
+    #[test]
+    fn served_is_any_jam_at_all() {
+        let useless = visit(&[Speed], Some(&[]));
+        let nothing = visit(&[Speed], None);
+
+        assert!(Condition::Served.holds(&[&useless]));
+        assert!(!Condition::Served.holds(&[&useless, &nothing]));
+        assert!(!Condition::Served.holds(&[]));
+    }
+
+    #[test]
+    fn delivered_and_ever_delivered() {
+        let speed = visit(&[Speed], Some(&[Speed]));
+        let flight = visit(&[Flight], Some(&[Flight]));
+        let history = [&speed, &flight];
+
+        assert!(Condition::Delivered(Flight).holds(&history));
+        assert!(!Condition::Delivered(Speed).holds(&history));
+        assert!(Condition::EverDelivered(Speed).holds(&history));
+        assert!(!Condition::EverDelivered(Hunger).holds(&history));
+
+        // spoiled jam doesn't do anything
+        let spoiled = Delivery {
+            spoiled: true,
+            ..visit(&[Hunger], Some(&[Hunger]))
+        };
+        assert!(!Condition::EverDelivered(Hunger).holds(&[&spoiled]));
+    }
+
+    #[test]
+    fn not_all_and_any() {
+        let met = visit(&[Speed], Some(&[Speed]));
+        let history = [&met];
+
+        assert!(!Condition::Not(Box::new(Condition::Met)).holds(&history));
+        assert!(Condition::Not(Box::new(Condition::Failed)).holds(&history));
+
+        let all = Condition::All(vec![Condition::Met, Condition::EverDelivered(Speed)]);
+        assert!(all.holds(&history));
+        let all = Condition::All(vec![Condition::Met, Condition::EverDelivered(Flight)]);
+        assert!(!all.holds(&history));
+
+        let any = Condition::Any(vec![Condition::Failed, Condition::EverDelivered(Speed)]);
+        assert!(any.holds(&history));
+        let any = Condition::Any(vec![Condition::Failed, Condition::EverDelivered(Flight)]);
+        assert!(!any.holds(&history));
+
+        assert!(Condition::All(vec![]).holds(&history));
+        assert!(!Condition::Any(vec![]).holds(&history));
+    }
+
+    const ARC: &str = r#"[
+        (
+            name: "Test",
+            customer: (hair: 0, face: 0, torso: 0),
+            starts_after: 1,
+            start: "first",
+            steps: {
+                "first": (
+                    story: "",
+                    requirements: [Speed],
+                    branches: [
+                        (when: Met, next: "thanks", after: 2),
+                        (when: Not(Served), next: "again"),
+                    ],
+                ),
+                "thanks": (story: "", requirements: []),
+                "again": (story: "", requirements: [Speed]),
+            },
+        ),
+    ]"#;
+
+    fn quest_log() -> QuestLog {
+        let arcs: Vec<QuestArc> = ron::de::from_str(ARC).unwrap();
+        for arc in &arcs {
+            arc.validate();
+        }
+        QuestLog::new(arcs)
+    }
+
+    fn next_step(quest_log: &mut QuestLog) -> Option<String> {
+        quest_log.next_visit().map(|visit| visit.id.step)
+    }
+
+    /// starts the arc and concludes its first step with `delivered`, gives the
+    /// steps of the next few visits
+    fn after_first(delivered: Option<&[JamEffect]>) -> Vec<Option<String>> {
+        let mut quest_log = quest_log();
+
+        assert_eq!(next_step(&mut quest_log), None);
+        assert_eq!(next_step(&mut quest_log), Some("first".to_string()));
+
+        let id = QuestStepId {
+            arc: 0,
+            step: "first".to_string(),
+        };
+        let history = DeliveryHistory(vec![Delivery {
+            quest: Some(id.clone()),
+            ..visit(&[Speed], delivered)
+        }]);
+        quest_log.conclude(&id, &history);
+
+        (0..4).map(|_| next_step(&mut quest_log)).collect()
+    }
+
+    #[test]
+    fn met_comes_back_later() {
+        assert_eq!(
+            after_first(Some(&[Speed])),
+            vec![None, None, Some("thanks".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn nothing_comes_straight_back() {
+        assert_eq!(
+            after_first(None),
+            vec![Some("again".to_string()), None, None, None]
+        );
+    }
+
+    #[test]
+    fn no_branch_ends_the_arc() {
+        assert_eq!(after_first(Some(&[Flight])), vec![None; 4]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::cauldron_scene::CauldronContents;
+use crate::jam::{JamEffect, JamIngredient};
+use crate::utils::load_data;
+
+pub struct RecipesPlugin;
+
+impl Plugin for RecipesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, setup.system())
+            .add_system(discover_recipes.system());
+    }
+}
+
+fn setup(commands: &mut Commands) {
+    let recipes: Vec<Recipe> = load_data("recipes.ron");
+
+    commands.insert_resource(RecipeBook {
+        discovered: vec![false; recipes.len()],
+        recipes,
+    });
+}
+
+/// a rule about the order ingredients go in, on top of their usual effects
+#[derive(Deserialize, Debug)]
+pub struct Recipe {
+    pub name: String,
+    pub when: Rule,
+    pub then: Outcome,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum Rule {
+    /// the first ingredient goes in at some point before the second
+    Before(JamIngredient, JamIngredient),
+    /// the ingredient is the last thing to go in
+    Last(JamIngredient),
+}
+
+impl Rule {
+    /// the index of the ingredient that completes the rule
+    fn matches(&self, ingredients: &[JamIngredient]) -> Option<usize> {
+        match self {
+            Rule::Before(first, then) => {
+                let first = ingredients.iter().position(|i| i == first)?;
+                let then = ingredients[first + 1..].iter().position(|i| i == then)?;
+
+                Some(first + 1 + then)
+            }
+            Rule::Last(last) => match ingredients.last() {
+                Some(i) if i == last => Some(ingredients.len() - 1),
+                _ => None,
+            },
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Rule::Before(first, then) => format!("{} before {}", first.name(), then.name()),
+            Rule::Last(last) => format!("{} added last", last.name()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub enum Outcome {
+    /// adds to the strength of an effect
+    Strengthen(JamEffect, u32),
+    /// cancels the effects of the ingredient that went in just before the one
+    /// completing the rule
+    CancelPrevious,
+}
+
+impl Outcome {
+    fn apply(
+        &self,
+        ingredients: &[JamIngredient],
+        trigger: usize,
+        strengths: &mut HashMap<JamEffect, u32>,
+    ) {
+        match self {
+            Outcome::Strengthen(effect, amount) => {
+                *strengths.entry(*effect).or_insert(0) += amount;
+            }
+            Outcome::CancelPrevious => {
+                let previous = match trigger.checked_sub(1) {
+                    Some(idx) => ingredients[idx],
+                    None => return,
+                };
+
+                for effect in previous.effects() {
+                    if let Some(strength) = strengths.get_mut(effect) {
+                        *strength = strength.saturating_sub(1);
+                    }
+                }
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Outcome::Strengthen(effect, _) => format!("makes {} stronger", effect.name()),
+            Outcome::CancelPrevious => "cancels out the ingredient before it".to_string(),
+        }
+    }
+}
+
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+    /// recipes show up in the jam book once they've happened in the cauldron
+    discovered: Vec<bool>,
+}
+
+impl RecipeBook {
+    fn matching<'a>(
+        &'a self,
+        ingredients: &'a [JamIngredient],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.recipes
+            .iter()
+            .enumerate()
+            .filter_map(move |(idx, r)| r.when.matches(ingredients).map(|t| (idx, t)))
+    }
+
+    /// changes `strengths` by every recipe the ingredients follow
+    pub fn apply(&self, ingredients: &[JamIngredient], strengths: &mut HashMap<JamEffect, u32>) {
+        for (idx, trigger) in self.matching(ingredients) {
+            self.recipes[idx]
+                .then
+                .apply(ingredients, trigger, strengths);
+        }
+    }
+
+    /// the name and a description of each discovered recipe
+    pub fn discovered(&self) -> impl Iterator<Item = (&str, String)> {
+        self.recipes
+            .iter()
+            .zip(&self.discovered)
+            .filter(|(_, discovered)| **discovered)
+            .map(|(r, _)| {
+                (
+                    r.name.as_str(),
+                    format!("{} {}", r.when.describe(), r.then.describe()),
+                )
+            })
+    }
+}
+
+fn discover_recipes(
+    mut book: ResMut<RecipeBook>,
+    q_contents: Query<&CauldronContents, Changed<CauldronContents>>,
+) {
+    for contents in q_contents.iter() {
+        let found: Vec<usize> = book.matching(&contents.0).map(|(idx, _)| idx).collect();
+
+        for idx in found {
+            book.discovered[idx] = true;
+        }
+    }
+}
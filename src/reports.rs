@@ -0,0 +1,273 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::deliveries::{CustomerLeftEvent, Delivery, DeliveryHistory};
+use crate::gamestate::{GameStage, GameState};
+use crate::jam::JamEffect;
+
+pub struct ReportsPlugin;
+
+impl Plugin for ReportsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Reports {
+            pending: vec![],
+            latest: None,
+        })
+        .on_state_enter(GameStage::Main, GameState::Main, setup.system())
+        .on_state_exit(GameStage::Main, GameState::Main, teardown.system())
+        .add_system(queue_reports.system())
+        .add_system(publish_reports.system());
+    }
+}
+
+struct NewsBoard;
+struct NewsBoardText;
+
+struct Report {
+    /// the report goes up once this many customers have left the shop
+    due: usize,
+    text: String,
+}
+
+struct Reports {
+    pending: Vec<Report>,
+    latest: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Outcome {
+    /// asked for and given
+    Worked,
+    /// given without being asked for
+    SideEffect,
+    /// asked for and not given
+    Missing,
+}
+
+static OPENERS: &[&str] = &[
+    "Word from the wastes: ",
+    "Scrawled on the board: ",
+    "A regular shouts across the shop: ",
+    "Overheard at the water pump: ",
+];
+
+fn phrase(effect: JamEffect, outcome: Outcome) -> &'static str {
+    match (effect, outcome) {
+        (JamEffect::NightVision, Outcome::Worked) => "they saw the ambush coming in the pitch dark",
+        (JamEffect::NightVision, Outcome::SideEffect) => {
+            "they couldn't sleep, the night was as bright as day"
+        }
+        (JamEffect::NightVision, Outcome::Missing) => {
+            "they stumbled around blind once the sun went down"
+        }
+
+        (JamEffect::SuperHumanStrength, Outcome::Worked) => {
+            "they tore a car door off with one hand"
+        }
+        (JamEffect::SuperHumanStrength, Outcome::SideEffect) => {
+            "they crushed every cup they tried to drink from"
+        }
+        (JamEffect::SuperHumanStrength, Outcome::Missing) => {
+            "they couldn't lift a finger when it mattered"
+        }
+
+        (JamEffect::Poison, Outcome::Worked) => "their enemies never woke up",
+        (JamEffect::Poison, Outcome::SideEffect) => {
+            "they spent three days retching behind the shop"
+        }
+        (JamEffect::Poison, Outcome::Missing) => {
+            "their target is still walking around, healthy as anything"
+        }
+
+        (JamEffect::Hunger, Outcome::Worked) => "they ate like a king for a week",
+        (JamEffect::Hunger, Outcome::SideEffect) => {
+            "they ate their whole crew's rations in one night"
+        }
+        (JamEffect::Hunger, Outcome::Missing) => "they couldn't face a single bite",
+
+        (JamEffect::GreaterHeal, Outcome::Worked) => "their wounds closed up overnight",
+        (JamEffect::GreaterHeal, Outcome::SideEffect) => {
+            "an old scar vanished and nobody recognises them"
+        }
+        (JamEffect::GreaterHeal, Outcome::Missing) => "their wounds still haven't healed",
+
+        (JamEffect::CureDisease, Outcome::Worked) => "the fever broke by morning",
+        (JamEffect::CureDisease, Outcome::SideEffect) => {
+            "they feel oddly healthy and don't trust it"
+        }
+        (JamEffect::CureDisease, Outcome::Missing) => "the sickness spread through the whole camp",
+
+        (JamEffect::Antivenom, Outcome::Worked) => "they shrugged off a snake bite like a mosquito",
+        (JamEffect::Antivenom, Outcome::SideEffect) => "they've taken to kissing scorpions for fun",
+        (JamEffect::Antivenom, Outcome::Missing) => "the venom got them in the end",
+
+        (JamEffect::Coagulant, Outcome::Worked) => {
+            "the bleeding stopped the second it touched skin"
+        }
+        (JamEffect::Coagulant, Outcome::SideEffect) => "the jam set solid inside the jar",
+        (JamEffect::Coagulant, Outcome::Missing) => "they bled all the way home",
+
+        (JamEffect::Flammable, Outcome::Worked) => "the fuel dump went up like the sun",
+        (JamEffect::Flammable, Outcome::SideEffect) => {
+            "their toast caught fire, and then their tent"
+        }
+        (JamEffect::Flammable, Outcome::Missing) => "they couldn't get so much as a spark going",
+
+        (JamEffect::Invisibility, Outcome::Worked) => "they walked right past the guards",
+        (JamEffect::Invisibility, Outcome::SideEffect) => {
+            "their crew left without them, nobody could see them"
+        }
+        (JamEffect::Invisibility, Outcome::Missing) => "the guards spotted them a mile off",
+
+        (JamEffect::Speed, Outcome::Worked) => "they outran a whole convoy on foot",
+        (JamEffect::Speed, Outcome::SideEffect) => "they ran straight past their own front door",
+        (JamEffect::Speed, Outcome::Missing) => "they were far too slow to get away",
+
+        (JamEffect::Flight, Outcome::Worked) => "they flew clean over the wall",
+        (JamEffect::Flight, Outcome::SideEffect) => {
+            "they drifted off in their sleep and woke up on a roof"
+        }
+        (JamEffect::Flight, Outcome::Missing) => "they never left the ground",
+
+        (JamEffect::HideousLaughter, Outcome::Worked) => "the whole raid party was in stitches",
+        (JamEffect::HideousLaughter, Outcome::SideEffect) => {
+            "it left their raid party laughing for hours"
+        }
+        (JamEffect::HideousLaughter, Outcome::Missing) => "nobody laughed at their joke",
+    }
+}
+
+/// the most interesting thing that happened, failures first then surprises,
+/// at most three of them
+fn gen_report(delivery: &Delivery) -> Option<String> {
+    let delivered = delivery.delivered.as_ref()?;
+    let mut rng = rand::thread_rng();
+
+    if delivery.spoiled {
+        return Some(format!(
+            "{}The jam had gone off, they were sick for a week.",
+            OPENERS.choose(&mut rng).unwrap()
+        ));
+    }
+
+    let missing = JamEffect::all()
+        .iter()
+        .filter(|e| delivery.requirements.contains(*e) && !delivered.contains(*e))
+        .map(|e| (*e, Outcome::Missing));
+    let side_effects = JamEffect::all()
+        .iter()
+        .filter(|e| !delivery.requirements.contains(*e) && delivered.contains(*e))
+        .map(|e| (*e, Outcome::SideEffect));
+    let worked = JamEffect::all()
+        .iter()
+        .filter(|e| delivery.requirements.contains(*e) && delivered.contains(*e))
+        .map(|e| (*e, Outcome::Worked));
+
+    let fragments: Vec<String> = missing
+        .chain(side_effects)
+        .chain(worked)
+        .take(3)
+        .map(|(effect, outcome)| format!("{} ({})", phrase(effect, outcome), effect.name()))
+        .collect();
+
+    if fragments.is_empty() {
+        return None;
+    }
+
+    let verdict = if delivery.met() {
+        "Your jam did the job"
+    } else {
+        "Your jam let them down"
+    };
+
+    Some(format!(
+        "{}{}, {}.",
+        OPENERS.choose(&mut rng).unwrap(),
+        verdict,
+        fragments.join(", and ")
+    ))
+}
+
+fn queue_reports(
+    mut reports: ResMut<Reports>,
+    history: Res<DeliveryHistory>,
+    mut event_reader: EventReader<CustomerLeftEvent>,
+) {
+    for CustomerLeftEvent(delivery) in event_reader.iter() {
+        if let Some(text) = gen_report(delivery) {
+            let due = history.0.len() + rand::thread_rng().gen_range(1..=3);
+            reports.pending.push(Report { due, text });
+        }
+    }
+}
+
+fn publish_reports(
+    mut reports: ResMut<Reports>,
+    history: Res<DeliveryHistory>,
+    mut q_text: Query<&mut Text, With<NewsBoardText>>,
+) {
+    let left = history.0.len();
+
+    if let Some(idx) = reports.pending.iter().position(|r| r.due <= left) {
+        let report = reports.pending.remove(idx);
+        reports.latest = Some(report.text);
+    }
+
+    if let Some(latest) = &reports.latest {
+        for mut text in q_text.iter_mut() {
+            if &text.sections[0].value != latest {
+                text.sections[0].value = latest.clone();
+            }
+        }
+    }
+}
+
+fn setup(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                max_size: Size::new(Val::Px(280.0), Val::Undefined),
+                padding: Rect::all(Val::Px(6.0)),
+                position: Rect {
+                    top: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            ..Default::default()
+        })
+        .with(NewsBoard)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    style: Style {
+                        max_size: Size::new(Val::Px(268.0), Val::Undefined),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        "No news from the wastes yet.",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 13.0,
+                            color: Color::WHITE,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .with(NewsBoardText);
+        });
+}
+
+fn teardown(commands: &mut Commands, q_board: Query<Entity, With<NewsBoard>>) {
+    for entity in q_board.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
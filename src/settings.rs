@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Settings::new(Difficulty::Normal))
+            .add_system(settings_keys.system());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+}
+
+/// the colours ingredients are drawn in, the colour-blind palettes keep every
+/// ingredient apart along the differences that are still easy to see
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Standard,
+    /// for protanopia and deuteranopia, ingredients differ in lightness and
+    /// from blue to yellow
+    RedGreen,
+    /// for tritanopia, ingredients differ in lightness and from red to green
+    BlueYellow,
+}
+
+impl Palette {
+    fn next(self) -> Self {
+        match self {
+            Palette::Standard => Palette::RedGreen,
+            Palette::RedGreen => Palette::BlueYellow,
+            Palette::BlueYellow => Palette::Standard,
+        }
+    }
+
+    /// whether to draw icons over the cauldron and jars as well
+    pub fn colour_blind(self) -> bool {
+        self != Palette::Standard
+    }
+}
+
+/// player options, assists default to on for easy and off otherwise but can
+/// each be flipped on their own
+pub struct Settings {
+    pub difficulty: Difficulty,
+    /// colour the parts of a customer's story that hint at an effect
+    pub highlight_keywords: bool,
+    /// show what the cauldron would make while brewing
+    pub preview_effects: bool,
+    pub palette: Palette,
+}
+
+impl Settings {
+    pub fn new(difficulty: Difficulty) -> Self {
+        let mut settings = Settings {
+            difficulty,
+            highlight_keywords: false,
+            preview_effects: false,
+            palette: Palette::Standard,
+        };
+        settings.set_difficulty(difficulty);
+        settings
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        let easy = difficulty == Difficulty::Easy;
+
+        self.difficulty = difficulty;
+        self.highlight_keywords = easy;
+        self.preview_effects = easy;
+    }
+}
+
+/// F1 swaps difficulty, F2 toggles keyword highlighting, F3 toggles the effects
+/// preview, F5 cycles through the colour palettes
+fn settings_keys(keys: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keys.just_pressed(KeyCode::F1) {
+        let difficulty = match settings.difficulty {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Easy,
+        };
+        settings.set_difficulty(difficulty);
+    }
+
+    if keys.just_pressed(KeyCode::F2) {
+        settings.highlight_keywords = !settings.highlight_keywords;
+    }
+
+    if keys.just_pressed(KeyCode::F3) {
+        settings.preview_effects = !settings.preview_effects;
+    }
+
+    if keys.just_pressed(KeyCode::F5) {
+        settings.palette = settings.palette.next();
+    }
+}
@@ -0,0 +1,701 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::ageing::{self, Aged, Ageing, GameClock};
+use crate::brewing::Brew;
+use crate::button;
+use crate::cauldron_scene::{CauldronContents, NoticeEvent};
+use crate::dragging::{self, Dragged, Hovered};
+use crate::gamestate::{GameStage, GameState};
+use crate::jam::{JamAssets, JamEffect, JamIngredient};
+use crate::recipes::RecipeBook;
+use crate::settings::{Palette, Settings};
+use crate::stations::{ActiveStation, Station};
+use crate::utils::load_data;
+use crate::viewport;
+
+pub struct ShelfPlugin;
+
+impl Plugin for ShelfPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, setup_assets.system())
+            .insert_resource(Shelf::default())
+            .on_state_enter(
+                GameStage::Main,
+                GameState::Cauldron,
+                spawn_bottle_button.system(),
+            )
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                handle_bottle_click.system(),
+            )
+            .on_state_exit(
+                GameStage::Main,
+                GameState::Cauldron,
+                teardown_bottle_button.system(),
+            )
+            .on_state_enter(GameStage::Main, GameState::Main, spawn_jars.system())
+            .on_state_update(GameStage::Main, GameState::Main, show_jar_label.system())
+            .on_state_update(GameStage::Main, GameState::Main, update_jar_icons.system())
+            .on_state_exit(GameStage::Main, GameState::Main, teardown_jars.system())
+            .add_system(redraw_jars.system());
+    }
+}
+
+/// how many jars fit on the shelf
+const SHELF_SLOTS: usize = 10;
+/// how many jars sit side by side before starting another row
+const SHELF_ROW: usize = 5;
+/// the most effect icons side by side on a jar with a colour-blind palette, any
+/// more go in rows above
+const JAR_ICONS: usize = 2;
+/// the gap between effect icons on a jar
+const JAR_ICON_SPACING: f32 = 18.0;
+
+#[derive(Deserialize, Debug)]
+pub struct JarSize {
+    pub name: String,
+    /// portions of jam it takes to fill
+    holds: u32,
+    /// points for a customer getting the jam they wanted
+    pub price: u64,
+    scale: f32,
+}
+
+/// how much jam a cauldron makes and the jars it can go in
+#[derive(Deserialize, Debug)]
+pub struct Bottling {
+    portions_per_ingredient: u32,
+    pub sizes: Vec<JarSize>,
+}
+
+impl Bottling {
+    fn validate(&self) {
+        if self.sizes.is_empty() {
+            panic!("jars.ron has no jar sizes");
+        }
+
+        for size in &self.sizes {
+            if size.holds == 0 {
+                panic!("jar size '{}' doesn't hold any jam", size.name);
+            }
+        }
+    }
+
+    /// the number of jars of a size a cauldron with this many ingredients fills
+    fn jars(&self, ingredients: usize, size: &JarSize) -> usize {
+        (ingredients as u32 * self.portions_per_ingredient / size.holds) as usize
+    }
+}
+
+/// a jar of jam taken out of a station, it keeps what it was made with
+#[derive(Debug, Clone)]
+pub struct BottledJam {
+    pub id: u32,
+    pub ingredients: Vec<JamIngredient>,
+    /// the strength of each effect when it was bottled, `Ageing` changes
+    /// them as the jar gets older
+    pub strengths: HashMap<JamEffect, u32>,
+    /// how it was cooked, for its colour
+    pub brew: Brew,
+    /// an index into `Bottling::sizes`
+    pub size: usize,
+    /// the number of the station it came from
+    pub station: usize,
+    /// in game hours, see `GameClock`
+    pub bottled_at: f64,
+}
+
+impl BottledJam {
+    /// the colour of the jam, worked out when it's drawn so it follows the
+    /// palette
+    pub fn colour(&self, palette: Palette) -> Color {
+        let contents = CauldronContents(self.ingredients.clone());
+        let colour = contents.colour(palette).unwrap_or(Color::NONE);
+
+        self.brew.tint(&contents, colour)
+    }
+
+    pub fn age(&self, clock: &GameClock) -> f64 {
+        clock.hours() - self.bottled_at
+    }
+
+    /// the jam as it is now
+    pub fn aged(&self, ageing: &Ageing, clock: &GameClock) -> Aged {
+        ageing.age(&self.ingredients, &self.strengths, self.age(clock))
+    }
+}
+
+/// the jars waiting to be given to customers, each has its own slot
+#[derive(Default)]
+pub struct Shelf {
+    slots: [Option<BottledJam>; SHELF_SLOTS],
+    next_id: u32,
+}
+
+impl Shelf {
+    /// puts `jars` jars of `jam` on the shelf, each with its own id. it's all
+    /// or nothing, `Err` has how many would have fitted
+    fn put(&mut self, jam: &BottledJam, jars: usize) -> Result<(), usize> {
+        let free: Vec<usize> = (0..SHELF_SLOTS)
+            .filter(|slot| self.slots[*slot].is_none())
+            .collect();
+        if free.len() < jars {
+            return Err(free.len());
+        }
+
+        for slot in free.into_iter().take(jars) {
+            self.slots[slot] = Some(BottledJam {
+                id: self.next_id,
+                ..jam.clone()
+            });
+            self.next_id += 1;
+        }
+
+        Ok(())
+    }
+
+    fn slot_of(&self, id: u32) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|j| j.as_ref().map_or(false, |j| j.id == id))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&BottledJam> {
+        self.slots.iter().flatten().find(|j| j.id == id)
+    }
+
+    /// takes the jar off the shelf
+    pub fn take(&mut self, id: u32) -> Option<BottledJam> {
+        let slot = self.slot_of(id)?;
+        self.slots[slot].take()
+    }
+}
+
+/// the sprite of a jar on the shelf in the shop
+pub struct ShelfJar(pub u32);
+/// the effects a jar's icons are drawn for, they change as it ages
+struct JarIcons(Vec<JamEffect>);
+struct JarIcon;
+
+/// the label shown while hovering over a jar
+struct JarLabel;
+struct BottleRow;
+/// bottles the cauldron into jars of this size
+struct BottleButton(usize);
+
+struct JarAssets {
+    filled: Handle<Texture>,
+    filling: Handle<Texture>,
+}
+
+fn setup_assets(commands: &mut Commands, asset_server: Res<AssetServer>) {
+    let filled = asset_server.load("sprites/fill_jar.png");
+    let filling = asset_server.load("sprites/fill_jar_filling.png");
+
+    commands.insert_resource(JarAssets { filled, filling });
+
+    let bottling: Bottling = load_data("jars.ron");
+    bottling.validate();
+
+    commands.insert_resource(bottling);
+}
+
+fn slot_position(slot: usize) -> Vec3 {
+    let (row, column) = (slot / SHELF_ROW, slot % SHELF_ROW);
+
+    Vec3::new(
+        -150.0 - 55.0 * column as f32,
+        -105.0 + 60.0 * row as f32,
+        14.0,
+    )
+}
+
+fn spawn_bottle_button(
+    commands: &mut Commands,
+    bottling: Res<Bottling>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                position: Rect {
+                    bottom: Val::Px(20.0),
+                    right: Val::Px(160.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .with(BottleRow)
+        .with_children(|parent| {
+            for (idx, size) in bottling.sizes.iter().enumerate() {
+                let label = format!("Bottle {}", size.name.to_lowercase());
+
+                button::spawn_text_button(parent, &mut *materials, font.clone(), &label, 16.0)
+                    .with(BottleButton(idx));
+            }
+        });
+}
+
+fn teardown_bottle_button(commands: &mut Commands, q_button: Query<Entity, With<BottleRow>>) {
+    for entity in q_button.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+/// fills as many jars as the station being shown makes and empties its
+/// cauldron, if they'll all fit on the shelf
+fn handle_bottle_click(
+    clock: Res<GameClock>,
+    bottling: Res<Bottling>,
+    active: Res<ActiveStation>,
+    recipes: Res<RecipeBook>,
+    mut shelf: ResMut<Shelf>,
+    mut q_station: Query<(&Station, &mut CauldronContents, &mut Brew)>,
+    q_button: Query<&BottleButton>,
+    mut ev_notice: ResMut<Events<NoticeEvent>>,
+    mut event_reader: EventReader<button::ButtonPressedEvent>,
+) {
+    for button::ButtonPressedEvent(entity) in event_reader.iter() {
+        if let Ok(BottleButton(size)) = q_button.get_component(*entity) {
+            let (station, mut contents, mut brew) = q_station.get_mut(active.0).unwrap();
+            let jar_size = &bottling.sizes[*size];
+
+            if contents.0.is_empty() {
+                ev_notice.send(NoticeEvent("There's nothing to bottle".to_string()));
+                continue;
+            }
+
+            let jars = bottling.jars(contents.0.len(), jar_size);
+            if jars == 0 {
+                ev_notice.send(NoticeEvent(format!(
+                    "There isn't enough jam for a {} jar",
+                    jar_size.name.to_lowercase()
+                )));
+                continue;
+            }
+
+            let jam = BottledJam {
+                id: 0,
+                ingredients: contents.0.clone(),
+                strengths: brew.strengths(&*contents, &*recipes),
+                brew: brew.clone(),
+                size: *size,
+                station: station.number,
+                bottled_at: clock.hours(),
+            };
+
+            // the batch stays in the cauldron until there's room for all of it
+            if let Err(room) = shelf.put(&jam, jars) {
+                let notice = match room {
+                    0 => "The shelf is full".to_string(),
+                    _ => format!(
+                        "There's only room on the shelf for {} of the {} jars",
+                        room, jars
+                    ),
+                };
+                ev_notice.send(NoticeEvent(notice));
+                continue;
+            }
+
+            contents.0.clear();
+            brew.reset();
+        }
+    }
+}
+
+fn spawn_jars(
+    commands: &mut Commands,
+    shelf: Res<Shelf>,
+    bottling: Res<Bottling>,
+    settings: Res<Settings>,
+    clock: Res<GameClock>,
+    ageing: Res<Ageing>,
+    jam_assets: Res<JamAssets>,
+    jar_assets: Res<JarAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    draw_jars(
+        commands,
+        &*shelf,
+        &*bottling,
+        settings.palette,
+        &*clock,
+        &*ageing,
+        &*jam_assets,
+        &*jar_assets,
+        &mut *materials,
+    );
+}
+
+/// jars are drawn for a palette, so any on the shelf are drawn again when it
+/// changes
+fn redraw_jars(
+    commands: &mut Commands,
+    mut drawn_with: Local<Option<Palette>>,
+    shelf: Res<Shelf>,
+    bottling: Res<Bottling>,
+    settings: Res<Settings>,
+    clock: Res<GameClock>,
+    ageing: Res<Ageing>,
+    jam_assets: Res<JamAssets>,
+    jar_assets: Res<JarAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_jar: Query<Entity, With<ShelfJar>>,
+) {
+    let palette = Some(settings.palette);
+    let changed = drawn_with.is_some() && *drawn_with != palette;
+    *drawn_with = palette;
+
+    if !changed || q_jar.iter().next().is_none() {
+        return;
+    }
+
+    for entity in q_jar.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    draw_jars(
+        commands,
+        &*shelf,
+        &*bottling,
+        settings.palette,
+        &*clock,
+        &*ageing,
+        &*jam_assets,
+        &*jar_assets,
+        &mut *materials,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_jars(
+    commands: &mut Commands,
+    shelf: &Shelf,
+    bottling: &Bottling,
+    palette: Palette,
+    clock: &GameClock,
+    ageing: &Ageing,
+    jam_assets: &JamAssets,
+    jar_assets: &JarAssets,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    for (slot, jam) in shelf.slots.iter().enumerate() {
+        let jam = match jam {
+            Some(jam) => jam,
+            None => continue,
+        };
+
+        let mut filling: ColorMaterial = jar_assets.filling.clone().into();
+        filling.color = jam.colour(palette);
+
+        let scale = bottling.sizes[jam.size].scale;
+
+        let icons = jar_icons(jam, palette, ageing, clock);
+
+        let entity = commands
+            .spawn(SpriteBundle {
+                material: materials.add(jar_assets.filled.clone().into()),
+                transform: Transform {
+                    translation: slot_position(slot),
+                    scale: Vec3::splat(scale),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(ShelfJar(jam.id))
+            .with(dragging::Hoverable)
+            .with(dragging::Draggable(dragging::DragKind::Jar))
+            .with(JarIcons(icons.clone()))
+            .current_entity()
+            .unwrap();
+
+        commands.with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                material: materials.add(filling),
+                transform: Transform::from_xyz(0.0, 0.0, 14.0),
+                ..Default::default()
+            });
+        });
+        spawn_jar_icons(commands, entity, &icons, jam_assets, materials);
+    }
+}
+
+/// with a colour-blind palette a jar also shows what it does
+fn jar_icons(
+    jam: &BottledJam,
+    palette: Palette,
+    ageing: &Ageing,
+    clock: &GameClock,
+) -> Vec<JamEffect> {
+    if !palette.colour_blind() {
+        return vec![];
+    }
+
+    let aged = jam.aged(ageing, clock);
+    JamEffect::all()
+        .iter()
+        .filter(|e| aged.effects.contains(e))
+        .copied()
+        .collect()
+}
+
+fn spawn_jar_icons(
+    commands: &mut Commands,
+    jar: Entity,
+    icons: &[JamEffect],
+    jam_assets: &JamAssets,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    for (row, effects) in icons.chunks(JAR_ICONS).enumerate() {
+        let middle = (effects.len() as f32 - 1.0) / 2.0;
+
+        for (idx, effect) in effects.iter().enumerate() {
+            let translation = Vec3::new(
+                JAR_ICON_SPACING * (idx as f32 - middle),
+                -8.0 + JAR_ICON_SPACING * row as f32,
+                15.0,
+            );
+
+            let icon = commands
+                .spawn(SpriteBundle {
+                    material: materials.add(effect.asset_for(jam_assets).into()),
+                    transform: Transform {
+                        translation,
+                        scale: Vec3::splat(0.5),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(JarIcon)
+                .current_entity()
+                .unwrap();
+
+            commands.push_children(jar, &[icon]);
+        }
+    }
+}
+
+/// jars lose and gain effects as they age, their icons follow
+fn update_jar_icons(
+    commands: &mut Commands,
+    shelf: Res<Shelf>,
+    settings: Res<Settings>,
+    clock: Res<GameClock>,
+    ageing: Res<Ageing>,
+    jam_assets: Res<JamAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut q_jar: Query<(Entity, &ShelfJar, &mut JarIcons, &Children)>,
+    q_icon: Query<(), With<JarIcon>>,
+) {
+    for (entity, ShelfJar(id), mut drawn, children) in q_jar.iter_mut() {
+        let jam = match shelf.get(*id) {
+            Some(jam) => jam,
+            None => continue,
+        };
+
+        let icons = jar_icons(jam, settings.palette, &*ageing, &*clock);
+        if icons == drawn.0 {
+            continue;
+        }
+
+        for child in children.iter().filter(|c| q_icon.get(**c).is_ok()) {
+            commands.despawn_recursive(*child);
+        }
+        spawn_jar_icons(commands, entity, &icons, &*jam_assets, &mut *materials);
+        drawn.0 = icons;
+    }
+}
+
+fn teardown_jars(
+    commands: &mut Commands,
+    q_jar: Query<Entity, With<ShelfJar>>,
+    q_label: Query<Entity, With<JarLabel>>,
+) {
+    for entity in q_jar.iter().chain(q_label.iter()) {
+        commands.despawn_recursive(entity);
+    }
+}
+
+/// a label with the jar's effects while the cursor is over it, rebuilt as the
+/// jar ages under it
+fn show_jar_label(
+    commands: &mut Commands,
+    mut shown: Local<Option<(u32, Aged, String)>>,
+    clock: Res<GameClock>,
+    ageing: Res<Ageing>,
+    shelf: Res<Shelf>,
+    bottling: Res<Bottling>,
+    asset_server: Res<AssetServer>,
+    jam_assets: Res<JamAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_hovered: Query<(&ShelfJar, &Transform), (With<Hovered>, Without<Dragged>)>,
+    q_label: Query<Entity, With<JarLabel>>,
+) {
+    let hovered = q_hovered
+        .iter()
+        .next()
+        .and_then(|(ShelfJar(id), t)| Some((*id, shelf.get(*id)?, t)));
+
+    let label = hovered.map(|(id, jam, _)| {
+        let aged = jam.aged(&*ageing, &*clock);
+        let mut age = ageing::describe_age(jam.age(&*clock));
+        if aged.spoiled {
+            age.push_str(", spoiled");
+        }
+        (id, aged, age)
+    });
+
+    if *shown == label {
+        return;
+    }
+    *shown = label.clone();
+
+    for entity in q_label.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    let ((_, jam, transform), (_, aged, age)) = match hovered.zip(label) {
+        Some(hovered) => hovered,
+        None => return,
+    };
+
+    // ui is positioned from the bottom left, the world from the middle, both
+    // are scaled to the window the same way
+    let left = transform.translation.x + viewport::WIDTH / 2.0 - 30.0;
+    let bottom = transform.translation.y + viewport::HEIGHT / 2.0 + 40.0;
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let ingredients: Vec<&str> = jam.ingredients.iter().map(|i| i.name()).collect();
+    let size = &bottling.sizes[jam.size];
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(4.0)),
+                position: Rect {
+                    left: Val::Px(left),
+                    bottom: Val::Px(bottom),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::rgb(0.95, 0.9, 0.8).into()),
+            ..Default::default()
+        })
+        .with(JarLabel)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    max_size: Size::new(Val::Px(180.0), Val::Undefined),
+                    ..Default::default()
+                },
+                text: Text {
+                    sections: vec![
+                        TextSection {
+                            value: format!(
+                                "{} jar from cauldron {}, {} points, {}",
+                                size.name, jam.station, size.price, age
+                            ),
+                            style: TextStyle {
+                                font: font.clone(),
+                                font_size: 13.0,
+                                color: Color::BLACK,
+                            },
+                        },
+                        TextSection {
+                            value: format!("\n{}", ingredients.join(", ")),
+                            style: TextStyle {
+                                font,
+                                font_size: 11.0,
+                                color: Color::BLACK,
+                            },
+                        },
+                    ],
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    material: materials.add(Color::NONE.into()),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for effect in JamEffect::all().iter().filter(|e| aged.effects.contains(e)) {
+                        parent.spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(20.0), Val::Px(20.0)),
+                                margin: Rect::all(Val::Px(1.0)),
+                                ..Default::default()
+                            },
+                            material: materials.add(effect.asset_for(&*jam_assets).into()),
+                            ..Default::default()
+                        });
+                    }
+                });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jam() -> BottledJam {
+        BottledJam {
+            id: 0,
+            ingredients: vec![],
+            strengths: HashMap::new(),
+            brew: Brew::default(),
+            size: 0,
+            station: 1,
+            bottled_at: 0.0,
+        }
+    }
+
+    fn jars(shelf: &Shelf) -> Vec<u32> {
+        shelf.slots.iter().flatten().map(|jam| jam.id).collect()
+    }
+
+    #[test]
+    fn puts_every_jar_with_its_own_id() {
+        let mut shelf = Shelf::default();
+
+        assert_eq!(shelf.put(&jam(), 3), Ok(()));
+        assert_eq!(shelf.put(&jam(), 2), Ok(()));
+        assert_eq!(jars(&shelf), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn puts_nothing_unless_it_all_fits() {
+        let mut shelf = Shelf::default();
+        shelf.put(&jam(), SHELF_SLOTS - 2).unwrap();
+        shelf.take(3);
+
+        // three free slots, one of them in the middle
+        assert_eq!(shelf.put(&jam(), 4), Err(3));
+        assert_eq!(jars(&shelf).len(), SHELF_SLOTS - 3);
+
+        assert_eq!(shelf.put(&jam(), 3), Ok(()));
+        assert_eq!(shelf.slots[3].as_ref().map(|jam| jam.id), Some(8));
+        assert_eq!(shelf.put(&jam(), 1), Err(0));
+    }
+}
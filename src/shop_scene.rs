@@ -3,22 +3,37 @@ use std::collections::HashSet;
 use bevy::prelude::*;
 use rand::{seq::SliceRandom, Rng};
 
-use crate::cauldron_scene::CauldronContents;
+use crate::ageing::{Ageing, GameClock};
+use crate::deliveries::{self, CustomerLeftEvent, Delivery, DeliveryHistory};
+use crate::dialogue;
 use crate::jam;
 use crate::jam::{JamEffect, JamIngredient};
-use crate::utils::average_colours;
+use crate::quests::{QuestLog, QuestStepId};
+use crate::shelf::{Bottling, Shelf, ShelfJar};
+use crate::speech_bubble::SpeechBubble;
 use crate::{
-    dragging::{self, DroppedOntoEvent},
+    dragging::{self, DragKind, DropTarget, DroppedOntoEvent},
     gamestate::{GameStage, GameState},
+    viewport,
 };
 
 pub struct ShopScenePlugin;
 
-struct StoryAssets {
+/// a piece of a customer's story, and the effect it hints at
+#[derive(Debug, Clone)]
+pub struct StorySegment {
+    pub text: String,
+    pub effect: Option<JamEffect>,
+}
+
+pub struct StoryAssets {
     story_timer: Timer,
-    story_text: String,
+    story: Vec<StorySegment>,
     story_requirements: HashSet<JamEffect>,
-    story_met: bool,
+    story_quest: Option<QuestStepId>,
+    /// whether the current customer has been given a jam (or is the welcome
+    /// text)
+    story_served: bool,
     char_move: Timer,
     char_delay: Timer,
     char_last_pos: Vec2,
@@ -27,8 +42,29 @@ struct StoryAssets {
     torso_idx: u32,
 }
 
+impl StoryAssets {
+    pub fn story(&self) -> &[StorySegment] {
+        &self.story
+    }
+
+    pub fn requirements(&self) -> &HashSet<JamEffect> {
+        &self.story_requirements
+    }
+
+    /// make the customer leave sooner
+    pub fn spend_patience(&mut self, seconds: f32) {
+        let elapsed = (self.story_timer.elapsed() + seconds).min(self.story_timer.duration());
+        self.story_timer.set_elapsed(elapsed);
+
+        let elapsed = (self.char_delay.elapsed() + seconds).min(self.char_delay.duration());
+        self.char_delay.set_elapsed(elapsed);
+    }
+}
+
 struct Story;
-struct JamJar;
+/// sent when a new customer walks up to the counter
+pub struct CustomerArrivedEvent;
+
 struct Score;
 struct Character;
 
@@ -36,7 +72,13 @@ struct Hair;
 struct Face;
 struct Torso;
 
-struct PlayerScore(u64);
+/// the player's money, jars sell for their price and it pays for upgrades
+pub struct PlayerScore(pub u64);
+/// how many customers have been given a jam that worked, it's never spent
+pub struct Reputation(pub u64);
+
+/// points lost for making a customer sick with spoiled jam
+const SICK_PENALTY: u64 = 2;
 
 static PHRASES: &[&[(Option<JamEffect>, &str)]] = &[
     /*Intro*/
@@ -181,26 +223,17 @@ struct Moveable {
 impl Plugin for ShopScenePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_startup_system_to_stage(StartupStage::PreStartup, setup_assets.system())
-            .add_startup_system_to_stage(StartupStage::PreStartup, setup_jam_jar_assets.system())
             .on_state_enter(GameStage::Main, GameState::Main, setup.system())
             .add_system(move_sprites.system())
             .add_system(move_character.system())
             .add_system(animate_sprites.system())
             .add_system(gen_story.system())
-            .on_state_update(
-                GameStage::Main,
-                GameState::Main,
-                jam_jar_clone_on_drag.system(),
-            )
-            .on_state_update(
-                GameStage::Main,
-                GameState::Main,
-                jam_jar_remove_on_drop.system(),
-            )
             .on_state_update(GameStage::Main, GameState::Main, handle_jam_drop.system())
-            .on_state_update(GameStage::Main, GameState::Main, recolour_jam_jar.system())
+            .on_state_update(GameStage::Main, GameState::Main, accept_jars.system())
             .on_state_exit(GameStage::Main, GameState::Main, teardown.system())
-            .insert_resource(PlayerScore(0));
+            .insert_resource(PlayerScore(0))
+            .insert_resource(Reputation(0))
+            .add_event::<CustomerArrivedEvent>();
     }
 }
 
@@ -221,9 +254,13 @@ fn setup_assets(commands: &mut Commands, asset_server: Res<AssetServer>) {
 
     commands.insert_resource(StoryAssets {
         story_timer,
-        story_text,
+        story: vec![StorySegment {
+            text: story_text,
+            effect: None,
+        }],
         story_requirements: HashSet::new(),
-        story_met: true,
+        story_quest: None,
+        story_served: true,
         char_move: Timer::from_seconds(5.0, true),
         char_delay: Timer::from_seconds(25.0, true),
         char_last_pos: Vec2::new(620.0, -130.0),
@@ -236,9 +273,9 @@ fn setup_assets(commands: &mut Commands, asset_server: Res<AssetServer>) {
 fn setup(
     commands: &mut Commands,
     asset_server: Res<AssetServer>,
+    score: Res<PlayerScore>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    story_assets: Res<StoryAssets>,
 ) {
     let shopfront_handle = asset_server.load("sprites/front.png");
     let shop_score_handle = asset_server.load("sprites/score_board.png");
@@ -263,6 +300,27 @@ fn setup(
     let torso_atlas = TextureAtlas::from_grid(torso_handle, Vec2::new(100.0, 100.0), 10, 1);
     let torso_atlas_handle = texture_atlases.add(torso_atlas);
 
+    // the icons of the effects the story hints at, laid over it
+    let story_icons = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Px(520.0), Val::Px(100.0)),
+                position: Rect {
+                    top: Val::Px(500.0),
+                    left: Val::Px(140.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            transform: Transform::from_xyz(0.0, 0.0, 6.0),
+            ..Default::default()
+        })
+        .with(Background)
+        .current_entity()
+        .unwrap();
+
     commands
         .spawn(SpriteBundle {
             material: materials.add(shop_score_handle.into()),
@@ -319,7 +377,9 @@ fn setup(
         })
         .with(Character)
         .with(Hair)
-        .with(dragging::DropTarget)
+        .with(dragging::DropTarget {
+            accepts: &[DragKind::Jar],
+        })
         .spawn(SpriteSheetBundle {
             texture_atlas: face_atlas_handle,
             transform: Transform::from_xyz(0.0, 0.0, 3.0),
@@ -334,7 +394,9 @@ fn setup(
         })
         .with(Character)
         .with(Face)
-        .with(dragging::DropTarget)
+        .with(dragging::DropTarget {
+            accepts: &[DragKind::Jar],
+        })
         .spawn(SpriteSheetBundle {
             texture_atlas: torso_atlas_handle,
             transform: Transform::from_xyz(0.0, 0.0, 2.0),
@@ -349,7 +411,9 @@ fn setup(
         })
         .with(Character)
         .with(Torso)
-        .with(dragging::DropTarget)
+        .with(dragging::DropTarget {
+            accepts: &[DragKind::Jar],
+        })
         .spawn(TextBundle {
             style: Style {
                 align_self: AlignSelf::Center,
@@ -369,7 +433,7 @@ fn setup(
             },
             transform: Transform::from_xyz(0.0, 0.0, 5.0),
             text: Text::with_section(
-                story_assets.story_text.to_string(),
+                "",
                 TextStyle {
                     font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                     font_size: 15.0,
@@ -384,6 +448,10 @@ fn setup(
         })
         .with(Background)
         .with(Story)
+        .with(Interaction::default())
+        .with(SpeechBubble::new(Vec2::new(520.0, 100.0)).with_icons(story_icons))
+        .with(viewport::ScalesOwnText)
+        .with(dialogue::DialogueBox)
         .spawn(TextBundle {
             style: Style {
                 align_self: AlignSelf::Center,
@@ -403,7 +471,7 @@ fn setup(
             },
             transform: Transform::from_xyz(0.0, 0.0, 5.0),
             text: Text::with_section(
-                0.to_string(),
+                score.0.to_string(),
                 TextStyle {
                     font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                     font_size: 100.0,
@@ -418,8 +486,6 @@ fn setup(
         })
         .with(Background)
         .with(Score);
-
-    spawn_jamjar(commands, &*asset_server, &mut *materials);
 }
 
 fn animate_sprites(
@@ -508,15 +574,18 @@ fn move_character(
         assets.char_move.unpause();
         assets.char_last_pos = Vec2::new(680.0, -180.0);
     }
-
 }
 
 fn gen_story(
     time: Res<Time>,
     mut assets: ResMut<StoryAssets>,
-    mut query: Query<(&mut Story, &mut Text)>,
+    mut quest_log: ResMut<QuestLog>,
+    mut history: ResMut<DeliveryHistory>,
+    mut ev_left: ResMut<Events<CustomerLeftEvent>>,
+    mut ev_arrived: ResMut<Events<CustomerArrivedEvent>>,
+    query: Query<&Story>,
 ) {
-    for (mut story, mut text) in query.iter_mut() {
+    for Story in query.iter() {
         if !assets
             .story_timer
             .tick(time.delta_seconds())
@@ -525,101 +594,125 @@ fn gen_story(
             return;
         }
 
-        let mut effects = Vec::new();
-        let mut temp_story = String::from("");
+        if !assets.story_served {
+            // they got bored of waiting
+            let delivery = Delivery {
+                quest: assets.story_quest.take(),
+                requirements: assets.story_requirements.clone(),
+                delivered: None,
+                spoiled: false,
+            };
+            deliveries::record(&mut *history, &mut *ev_left, delivery);
+        }
+
+        assets.story_served = false;
+
+        if let Some(visit) = quest_log.next_visit() {
+            assets.story = vec![StorySegment {
+                text: visit.step.story.clone(),
+                effect: None,
+            }];
+            assets.story_requirements = visit.step.requirements.iter().cloned().collect();
+            assets.story_quest = Some(visit.id);
+
+            assets.hair_idx = visit.customer.hair;
+            assets.face_idx = visit.customer.face;
+            assets.torso_idx = visit.customer.torso;
+
+            ev_arrived.send(CustomerArrivedEvent);
+            return;
+        }
+
+        let mut temp_story = Vec::new();
         for x in 0..13 {
             let (effect, text_fragment) = PHRASES[x].choose(&mut rand::thread_rng()).unwrap();
-            temp_story.push_str(text_fragment);
-            if let Some(effect) = effect {
-                effects.push(effect);
-            }
+            temp_story.push(StorySegment {
+                text: text_fragment.to_string(),
+                effect: *effect,
+            });
         }
-        temp_story.push_str("As you can tell, I am in deperate need of assistance, do you have any jam that could help me ensure this doesn't happen again?");
-        assets.story_text = temp_story;
-        assets.story_requirements = effects.into_iter().cloned().collect();
-        assets.story_met = false;
-        text.sections[0].value = format!("{:2}", assets.story_text);
+        temp_story.push(StorySegment {
+            text: "As you can tell, I am in deperate need of assistance, do you have any jam that could help me ensure this doesn't happen again?".to_string(),
+            effect: None,
+        });
+        assets.story_requirements = temp_story.iter().filter_map(|s| s.effect).collect();
+        assets.story = temp_story;
+        assets.story_quest = None;
 
         assets.hair_idx = rand::thread_rng().gen_range(0..10);
         assets.face_idx = rand::thread_rng().gen_range(0..10);
         assets.torso_idx = rand::thread_rng().gen_range(0..10);
-    }
-}
 
-fn spawn_jamjar(
-    commands: &mut Commands,
-    asset_server: &AssetServer,
-    materials: &mut Assets<ColorMaterial>,
-) {
-    let jam_jar = asset_server.load("sprites/jam_jar.png");
-
-    commands
-        .spawn(SpriteBundle {
-            material: materials.add(jam_jar.into()),
-            transform: Transform::from_xyz(-150.0, -105.0, 14.0),
-            ..Default::default()
-        })
-        .with(JamJar)
-        .with(Background)
-        .with(dragging::Hoverable)
-        .with(dragging::Draggable);
-}
-
-fn jam_jar_clone_on_drag(
-    commands: &mut Commands,
-    q_jamjar: Query<&JamJar>,
-    asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut event_reader: EventReader<dragging::DraggedEvent>,
-) {
-    for dragging::DraggedEvent(entity) in event_reader.iter() {
-        if let Ok(JamJar) = q_jamjar.get_component(*entity) {
-            spawn_jamjar(commands, &*asset_server, &mut *materials);
-        }
+        ev_arrived.send(CustomerArrivedEvent);
     }
 }
 
-fn jam_jar_remove_on_drop(
-    commands: &mut Commands,
-    q_jam_ingredient: Query<&JamJar>,
-    mut event_reader: EventReader<dragging::DroppedEvent>,
-) {
-    for dragging::DroppedEvent(entity) in event_reader.iter() {
-        if let Ok(JamJar) = q_jam_ingredient.get_component(*entity) {
-            commands.despawn_recursive(*entity);
+/// the customer only takes a jar while they're waiting for one, otherwise it
+/// goes back on the shelf
+fn accept_jars(story: Res<StoryAssets>, mut q_target: Query<&mut DropTarget, With<Character>>) {
+    let accepts: &'static [DragKind] = if story.story_served {
+        &[]
+    } else {
+        &[DragKind::Jar]
+    };
+
+    for mut target in q_target.iter_mut() {
+        if target.accepts != accepts {
+            target.accepts = accepts;
         }
     }
 }
 
 fn handle_jam_drop(
     mut score: ResMut<PlayerScore>,
-    contents: Res<CauldronContents>,
+    mut reputation: ResMut<Reputation>,
+    mut shelf: ResMut<Shelf>,
+    bottling: Res<Bottling>,
+    ageing: Res<Ageing>,
+    clock: Res<GameClock>,
     mut story: ResMut<StoryAssets>,
-    q_jam_jar: Query<&JamJar>,
+    mut history: ResMut<DeliveryHistory>,
+    mut ev_left: ResMut<Events<CustomerLeftEvent>>,
+    q_jar: Query<&ShelfJar>,
     q_character: Query<&Character>,
     mut q_score: Query<&mut Text, With<Score>>,
     mut event_reader: EventReader<DroppedOntoEvent>,
 ) {
-    if story.story_met {
+    if story.story_served {
         return;
     }
 
     for DroppedOntoEvent { src, dst } in event_reader.iter() {
-        if let (Ok(JamJar), Ok(Character)) = (
-            q_jam_jar.get_component(*src),
+        if let (Ok(ShelfJar(id)), Ok(Character)) = (
+            q_jar.get_component::<ShelfJar>(*src),
             q_character.get_component(*dst),
         ) {
-            let effects = JamIngredient::calculate_effects(&contents.0);
-
-            if story.story_requirements.is_subset(&effects) {
-                story.story_met = true;
-                score.0 += 1;
+            let jam = match shelf.take(*id) {
+                Some(jam) => jam,
+                None => continue,
+            };
+            let aged = jam.aged(&*ageing, &*clock);
+            let delivery = Delivery {
+                quest: story.story_quest.take(),
+                requirements: story.story_requirements.clone(),
+                delivered: Some(aged.effects),
+                spoiled: aged.spoiled,
+            };
+
+            if delivery.met() {
+                score.0 += bottling.sizes[jam.size].price;
+                reputation.0 += 1;
+            } else if delivery.spoiled {
+                score.0 = score.0.saturating_sub(SICK_PENALTY);
+            }
 
-                for mut text in q_score.iter_mut() {
-                    text.sections[0].value = score.0.to_string();
-                }
+            for mut text in q_score.iter_mut() {
+                text.sections[0].value = score.0.to_string();
             }
 
+            story.story_served = true;
+            deliveries::record(&mut *history, &mut *ev_left, delivery);
+
             let duration = story.char_delay.duration();
             story.char_delay.set_elapsed(duration);
 
@@ -628,82 +721,3 @@ fn handle_jam_drop(
         }
     }
 }
-
-struct JamJarAssets {
-    normal: Handle<Texture>,
-    filled: Handle<Texture>,
-    bg: Handle<Texture>,
-}
-
-fn setup_jam_jar_assets(commands: &mut Commands, asset_server: Res<AssetServer>) {
-    let normal = asset_server.load("sprites/jam_jar.png");
-    let filled = asset_server.load("sprites/fill_jar.png");
-    let bg = asset_server.load("sprites/fill_jar_filling.png");
-
-    commands.insert_resource(JamJarAssets { normal, filled, bg });
-}
-
-fn recolour_jam_jar(
-    commands: &mut Commands,
-    contents: Res<CauldronContents>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    jam_jar_assets: Res<JamJarAssets>,
-    mut q_material: Query<&mut Handle<ColorMaterial>>,
-    mut q_jamjar: Query<(Entity, Option<&Children>), With<JamJar>>,
-) {
-    let colour = average_colours(contents.0.iter().map(|i| i.colour()));
-
-    if colour.a() < 0.5 {
-        for (entity, children) in q_jamjar.iter_mut() {
-            let mut material = q_material.get_component_mut(entity).unwrap();
-
-            if let Some(children) = children {
-                for entity in children.as_ref() {
-                    commands.despawn(*entity);
-                }
-
-                if !children.is_empty() {
-                    *material = materials.add(jam_jar_assets.normal.clone().into());
-                }
-            }
-        }
-    } else {
-        for (entity, children) in q_jamjar.iter_mut() {
-            let mut material = q_material.get_component_mut(entity).unwrap();
-
-            if let Some(children) = children {
-                for child in children.as_ref() {
-                    let bg = q_material
-                        .get_component::<Handle<ColorMaterial>>(*child)
-                        .unwrap();
-
-                    let mat = materials.get_mut(bg).unwrap();
-                    mat.color.set_r(colour.r());
-                    mat.color.set_g(colour.g());
-                    mat.color.set_b(colour.b());
-                    mat.color.set_a(colour.a());
-                }
-            } else {
-                let bg = materials.add(jam_jar_assets.bg.clone().into());
-                let mat = materials.get_mut(&bg).unwrap();
-                mat.color.set_r(colour.r());
-                mat.color.set_g(colour.g());
-                mat.color.set_b(colour.b());
-                mat.color.set_a(colour.a());
-
-                let child = commands
-                    .spawn(SpriteBundle {
-                        material: bg,
-                        transform: Transform::from_xyz(0.0, 0.0, 14.0),
-                        ..Default::default()
-                    })
-                    .current_entity()
-                    .unwrap();
-
-                commands.push_children(entity, &[child]);
-
-                *material = materials.add(jam_jar_assets.filled.clone().into());
-            }
-        }
-    }
-}
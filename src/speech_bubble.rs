@@ -0,0 +1,478 @@
+use ab_glyph::{Font as _, FontArc, PxScale, ScaleFont};
+use bevy::prelude::*;
+
+use crate::viewport::Viewport;
+
+pub struct SpeechBubblePlugin;
+
+impl Plugin for SpeechBubblePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(paginate_bubbles.system())
+            .add_system(advance_bubbles.system())
+            .add_system(type_bubbles.system())
+            .add_system(show_bubble_icons.system());
+    }
+}
+
+/// seconds between each character being typed out
+const CHAR_DELAY: f32 = 0.02;
+/// the size of the icons tagged on to spans
+const ICON_SIZE: f32 = 16.0;
+/// space either side of an icon
+const ICON_GAP: f32 = 2.0;
+
+/// a run of text drawn in one colour, `None` uses the colour of the text node,
+/// with an icon after it once it's been typed out
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub text: String,
+    pub colour: Option<Color>,
+    pub icon: Option<Handle<Texture>>,
+}
+
+impl Span {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Span {
+            text: text.into(),
+            colour: None,
+            icon: None,
+        }
+    }
+}
+
+/// an icon tagged on to the end of a span
+#[derive(Debug, Clone)]
+pub struct PageIcon {
+    pub texture: Handle<Texture>,
+    /// from the top left of the bubble
+    pub offset: Vec2,
+    /// how many characters of the page are typed out before it shows
+    pub after: usize,
+}
+
+/// the spans that fit in the bubble at once, and where their icons go
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub spans: Vec<Span>,
+    pub icons: Vec<PageIcon>,
+}
+
+/// text shown a page at a time in a fixed size box, each page is typed out a
+/// character at a time
+///
+/// clicking the box (it needs an `Interaction`) or pressing space/enter shows
+/// the rest of the page, or turns to the next one
+///
+/// text nodes can't have children, so icons go in a separate node laid over
+/// the bubble, see `with_icons`
+///
+/// the text node needs `viewport::ScalesOwnText`, the bubble is laid out at the
+/// sizes it was made with and scales the sections it writes
+pub struct SpeechBubble {
+    size: Vec2,
+    /// the style of the text node's first section, taken before we start
+    /// replacing its sections, at its unscaled size
+    style: Option<TextStyle>,
+    /// pages that were asked for, these get split up further to fit
+    source: Vec<Vec<Span>>,
+    pages: Vec<Page>,
+    page: usize,
+    shown: usize,
+    /// seconds spent typing that haven't shown a character yet
+    typing: f32,
+    paginated: bool,
+    /// the (page, shown, viewport scale) last written to the text node
+    rendered: Option<(usize, usize, f32)>,
+    icon_layer: Option<Entity>,
+    icons: Vec<Entity>,
+    /// the page and number of icons last shown
+    icons_shown: Option<(usize, usize)>,
+}
+
+impl SpeechBubble {
+    pub fn new(size: Vec2) -> Self {
+        SpeechBubble {
+            size,
+            style: None,
+            source: vec![],
+            pages: vec![],
+            page: 0,
+            shown: 0,
+            typing: 0.0,
+            paginated: false,
+            rendered: None,
+            icon_layer: None,
+            icons: vec![],
+            icons_shown: None,
+        }
+    }
+
+    /// puts the icons of spans in `layer`, a node the size of the bubble laid
+    /// over it at the same position
+    pub fn with_icons(mut self, layer: Entity) -> Self {
+        self.icon_layer = Some(layer);
+        self
+    }
+
+    /// always breaks between the given pages
+    pub fn set_pages(&mut self, pages: Vec<Vec<Span>>) {
+        self.source = pages;
+        self.pages.clear();
+        self.page = 0;
+        self.shown = 0;
+        self.paginated = false;
+        self.rendered = None;
+        self.icons_shown = None;
+    }
+
+    fn page_len(&self) -> usize {
+        self.pages
+            .get(self.page)
+            .map_or(0, |p| p.spans.iter().map(|s| s.text.chars().count()).sum())
+    }
+
+    fn page_done(&self) -> bool {
+        self.shown >= self.page_len()
+    }
+
+    /// the last page has been typed out completely
+    pub fn finished(&self) -> bool {
+        self.paginated && self.page + 1 >= self.pages.len() && self.page_done()
+    }
+
+    fn advance(&mut self) {
+        if !self.page_done() {
+            self.shown = self.page_len();
+        } else if self.page + 1 < self.pages.len() {
+            self.page += 1;
+            self.shown = 0;
+        }
+    }
+
+    fn visible_spans(&self) -> Vec<Span> {
+        let mut remaining = self.shown;
+        let mut spans = vec![];
+
+        for span in self.pages.get(self.page).into_iter().flat_map(|p| &p.spans) {
+            if remaining == 0 {
+                break;
+            }
+
+            let text: String = span.text.chars().take(remaining).collect();
+            remaining -= text.chars().count();
+            spans.push(Span {
+                text,
+                colour: span.colour,
+                icon: None,
+            });
+        }
+
+        spans
+    }
+
+    fn visible_icons(&self) -> Vec<PageIcon> {
+        self.pages
+            .get(self.page)
+            .into_iter()
+            .flat_map(|p| &p.icons)
+            .filter(|icon| icon.after <= self.shown)
+            .cloned()
+            .collect()
+    }
+}
+
+fn text_width(font: &FontArc, scale: PxScale, text: &str) -> f32 {
+    let font = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut last = None;
+
+    for c in text.chars() {
+        let glyph = font.glyph_id(c);
+        if let Some(last) = last {
+            width += font.kern(last, glyph);
+        }
+        width += font.h_advance(glyph);
+        last = Some(glyph);
+    }
+
+    width
+}
+
+/// a word to lay out, the last word of a span with an icon is followed by
+/// enough spaces to fit it
+struct Word<'a> {
+    text: &'a str,
+    colour: Option<Color>,
+    icon: Option<&'a Handle<Texture>>,
+}
+
+/// word wrap `spans` to fit `size` and split them into pages of as many lines
+/// as fit, each page has its lines joined by newlines so the layout matches
+///
+/// lines are centred, so icons are placed from the width of their line
+pub fn paginate(font: &FontArc, font_size: f32, size: Vec2, spans: &[Span]) -> Vec<Page> {
+    let scale = PxScale::from(font_size);
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+    let lines_per_page = ((size.y / line_height).floor() as usize).max(1);
+
+    let space = text_width(font, scale, " ").max(1.0);
+    let pad = " ".repeat(((ICON_SIZE + ICON_GAP * 2.0) / space).ceil() as usize);
+
+    let words = spans.iter().flat_map(|s| {
+        let words: Vec<&str> = s.text.split_whitespace().collect();
+        let last = words.len().saturating_sub(1);
+
+        words.into_iter().enumerate().map(move |(idx, text)| Word {
+            text,
+            colour: s.colour,
+            icon: s.icon.as_ref().filter(|_| idx == last),
+        })
+    });
+
+    let padded = |word: &Word| match word.icon {
+        Some(_) => format!("{}{}", word.text, pad),
+        None => word.text.to_string(),
+    };
+
+    let mut lines: Vec<Vec<Word>> = vec![];
+    let mut line: Vec<Word> = vec![];
+    let mut line_text = String::new();
+
+    for word in words {
+        let candidate = if line.is_empty() {
+            padded(&word)
+        } else {
+            format!("{} {}", line_text, padded(&word))
+        };
+
+        if !line.is_empty() && text_width(font, scale, &candidate) > size.x {
+            lines.push(std::mem::take(&mut line));
+            line_text = padded(&word);
+        } else {
+            line_text = candidate;
+        }
+
+        line.push(word);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+        .chunks(lines_per_page)
+        .map(|page| {
+            let mut spans: Vec<Span> = vec![];
+            let mut icons: Vec<PageIcon> = vec![];
+            let mut typed = 0;
+
+            let widths: Vec<f32> = page
+                .iter()
+                .map(|line| {
+                    let text: Vec<String> = line.iter().map(&padded).collect();
+                    text_width(font, scale, &text.join(" "))
+                })
+                .collect();
+            // the text node is as wide as its longest line
+            let page_width = widths.iter().cloned().fold(0.0, f32::max);
+
+            for (line_idx, (line, line_width)) in page.iter().zip(&widths).enumerate() {
+                let mut line_text = String::new();
+
+                for (word_idx, word) in line.iter().enumerate() {
+                    let sep = match (line_idx, word_idx) {
+                        (0, 0) => "",
+                        (_, 0) => "\n",
+                        _ => " ",
+                    };
+
+                    if word_idx > 0 {
+                        line_text.push(' ');
+                    }
+                    line_text.push_str(word.text);
+                    typed += sep.chars().count() + word.text.chars().count();
+
+                    if let Some(texture) = word.icon {
+                        icons.push(PageIcon {
+                            texture: texture.clone(),
+                            offset: Vec2::new(
+                                (page_width - line_width) / 2.0
+                                    + text_width(font, scale, &line_text)
+                                    + ICON_GAP,
+                                line_idx as f32 * line_height + (line_height - ICON_SIZE) / 2.0,
+                            ),
+                            after: typed,
+                        });
+                        line_text.push_str(&pad);
+                    }
+
+                    let text = padded(word);
+                    typed += text.chars().count() - word.text.chars().count();
+
+                    match spans.last_mut() {
+                        Some(last) if last.colour == word.colour => {
+                            last.text.push_str(sep);
+                            last.text.push_str(&text);
+                        }
+                        _ => spans.push(Span {
+                            text: format!("{}{}", sep, text),
+                            colour: word.colour,
+                            icon: None,
+                        }),
+                    }
+                }
+            }
+
+            Page { spans, icons }
+        })
+        .collect()
+}
+
+fn paginate_bubbles(fonts: Res<Assets<Font>>, mut q_bubble: Query<(&mut SpeechBubble, &Text)>) {
+    for (mut bubble, text) in q_bubble.iter_mut() {
+        if bubble.paginated {
+            continue;
+        }
+
+        if bubble.style.is_none() {
+            bubble.style = Some(text.sections[0].style.clone());
+        }
+
+        let style = bubble.style.clone().unwrap();
+        let font = match fonts.get(&style.font) {
+            Some(font) => font,
+            // try again once it's loaded
+            None => continue,
+        };
+
+        let size = bubble.size;
+        let pages: Vec<Page> = bubble
+            .source
+            .iter()
+            .flat_map(|source| paginate(&font.font, style.font_size, size, source))
+            .collect();
+
+        bubble.pages = pages;
+        bubble.paginated = true;
+    }
+}
+
+fn advance_bubbles(
+    keys: Res<Input<KeyCode>>,
+    q_clicked: Query<&Interaction, Mutated<Interaction>>,
+    mut q_bubble: Query<(Entity, &mut SpeechBubble)>,
+) {
+    let key_pressed = keys.just_pressed(KeyCode::Space) || keys.just_pressed(KeyCode::Return);
+
+    for (entity, mut bubble) in q_bubble.iter_mut() {
+        let clicked = matches!(q_clicked.get(entity), Ok(Interaction::Clicked));
+
+        if key_pressed || clicked {
+            bubble.advance();
+        }
+    }
+}
+
+fn type_bubbles(
+    time: Res<Time>,
+    viewport: Res<Viewport>,
+    mut q_bubble: Query<(&mut SpeechBubble, &mut Text)>,
+) {
+    for (mut bubble, mut text) in q_bubble.iter_mut() {
+        if !bubble.paginated {
+            continue;
+        }
+
+        if bubble.page_done() {
+            bubble.typing = 0.0;
+        } else {
+            bubble.typing += time.delta_seconds();
+
+            // a slow frame can owe several characters
+            while bubble.typing >= CHAR_DELAY && !bubble.page_done() {
+                bubble.typing -= CHAR_DELAY;
+                bubble.shown += 1;
+            }
+        }
+
+        let state = Some((bubble.page, bubble.shown, viewport.scale));
+        if bubble.rendered == state {
+            continue;
+        }
+        bubble.rendered = state;
+
+        let base = bubble.style.clone().unwrap();
+        let style = TextStyle {
+            font_size: base.font_size * viewport.scale,
+            ..base
+        };
+        let mut sections: Vec<TextSection> = bubble
+            .visible_spans()
+            .into_iter()
+            .map(|span| TextSection {
+                value: span.text,
+                style: TextStyle {
+                    color: span.colour.unwrap_or(style.color),
+                    ..style.clone()
+                },
+            })
+            .collect();
+
+        if sections.is_empty() {
+            sections.push(TextSection {
+                value: String::new(),
+                style,
+            });
+        }
+
+        text.sections = sections;
+    }
+}
+
+fn show_bubble_icons(
+    commands: &mut Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut q_bubble: Query<&mut SpeechBubble>,
+) {
+    for mut bubble in q_bubble.iter_mut() {
+        let layer = match bubble.icon_layer {
+            Some(layer) => layer,
+            None => continue,
+        };
+
+        let visible = bubble.visible_icons();
+        let state = Some((bubble.page, visible.len()));
+        if bubble.icons_shown == state {
+            continue;
+        }
+        bubble.icons_shown = state;
+
+        for icon in bubble.icons.drain(..) {
+            commands.despawn_recursive(icon);
+        }
+
+        for icon in visible {
+            let entity = commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            left: Val::Px(icon.offset.x),
+                            top: Val::Px(icon.offset.y),
+                            ..Default::default()
+                        },
+                        size: Size::new(Val::Px(ICON_SIZE), Val::Px(ICON_SIZE)),
+                        ..Default::default()
+                    },
+                    material: materials.add(icon.texture.into()),
+                    ..Default::default()
+                })
+                .current_entity()
+                .unwrap();
+
+            commands.push_children(layer, &[entity]);
+            bubble.icons.push(entity);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+use crate::brewing::Brew;
+use crate::button;
+use crate::cauldron_scene::{CauldronCapacity, CauldronContents, NoticeEvent};
+use crate::gamestate::{GameStage, GameState};
+use crate::shop_scene::PlayerScore;
+
+pub struct StationsPlugin;
+
+impl Plugin for StationsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, setup.system())
+            .on_state_update(
+                GameStage::Main,
+                GameState::Cauldron,
+                handle_tab_click.system(),
+            )
+            .on_state_update(GameStage::Main, GameState::Cauldron, show_tabs.system())
+            .on_state_exit(GameStage::Main, GameState::Cauldron, teardown.system());
+    }
+}
+
+/// the most stations the shop can have
+const MAX_STATIONS: usize = 4;
+/// score spent on the second station, each one after costs this much more
+const STATION_COST: u64 = 5;
+
+/// a cauldron the player can brew in, it has its own `CauldronContents`,
+/// `CauldronCapacity` and `Brew`
+pub struct Station {
+    /// counting from 1, in the order they were bought
+    pub number: usize,
+}
+
+/// the station shown in the cauldron scene
+pub struct ActiveStation(pub Entity);
+
+struct StationTabs;
+struct StationTab(Entity);
+struct BuyStationButton;
+
+fn spawn_station(commands: &mut Commands, number: usize) -> Entity {
+    commands
+        .spawn((
+            Station { number },
+            CauldronContents(vec![]),
+            CauldronCapacity::default(),
+            Brew::default(),
+        ))
+        .current_entity()
+        .unwrap()
+}
+
+fn setup(commands: &mut Commands) {
+    let station = spawn_station(commands, 1);
+
+    commands.insert_resource(ActiveStation(station));
+}
+
+fn teardown(commands: &mut Commands, q_tabs: Query<Entity, With<StationTabs>>) {
+    for entity in q_tabs.iter() {
+        commands.despawn_recursive(entity);
+    }
+}
+
+fn station_cost(stations: usize) -> u64 {
+    STATION_COST * stations as u64
+}
+
+fn handle_tab_click(
+    commands: &mut Commands,
+    mut active: ResMut<ActiveStation>,
+    mut score: ResMut<PlayerScore>,
+    q_station: Query<&Station>,
+    q_tab: Query<&StationTab>,
+    q_buy: Query<&BuyStationButton>,
+    mut ev_notice: ResMut<Events<NoticeEvent>>,
+    mut event_reader: EventReader<button::ButtonPressedEvent>,
+) {
+    for button::ButtonPressedEvent(entity) in event_reader.iter() {
+        if let Ok(StationTab(station)) = q_tab.get_component(*entity) {
+            active.0 = *station;
+        }
+
+        if let Ok(BuyStationButton) = q_buy.get_component(*entity) {
+            let stations = q_station.iter().count();
+            let cost = station_cost(stations);
+
+            if score.0 < cost {
+                ev_notice.send(NoticeEvent(format!(
+                    "You need {} points for another cauldron",
+                    cost
+                )));
+                continue;
+            }
+
+            // it shows up as a new tab once it's spawned
+            score.0 -= cost;
+            spawn_station(commands, stations + 1);
+        }
+    }
+}
+
+/// a tab for each station along the top, rebuilt when one is bought or picked
+fn show_tabs(
+    commands: &mut Commands,
+    mut shown: Local<Option<(Entity, usize)>>,
+    active: Res<ActiveStation>,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_station: Query<(Entity, &Station)>,
+    q_tabs: Query<Entity, With<StationTabs>>,
+) {
+    let mut stations: Vec<(Entity, &Station)> = q_station.iter().collect();
+    stations.sort_by_key(|(_, s)| s.number);
+
+    let state = Some((active.0, stations.len()));
+    if *shown == state && q_tabs.iter().next().is_some() {
+        return;
+    }
+    *shown = state;
+
+    for entity in q_tabs.iter() {
+        commands.despawn_recursive(entity);
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                position: Rect {
+                    top: Val::Px(60.0),
+                    left: Val::Px(300.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .with(StationTabs)
+        .with_children(|parent| {
+            for (entity, station) in &stations {
+                let label = if *entity == active.0 {
+                    format!("[{}]", station.number)
+                } else {
+                    station.number.to_string()
+                };
+
+                button::spawn_text_button(parent, &mut *materials, font.clone(), &label, 15.0)
+                    .with(StationTab(*entity));
+            }
+
+            if stations.len() < MAX_STATIONS {
+                let label = format!("New cauldron - {} points", station_cost(stations.len()));
+
+                button::spawn_text_button(parent, &mut *materials, font.clone(), &label, 15.0)
+                    .with(BuyStationButton);
+            }
+        });
+}
@@ -0,0 +1,193 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::brewing::Brew;
+use crate::cauldron_scene::{Cauldron, CauldronContents};
+use crate::dragging::{CursorState, Dragged};
+use crate::gamestate::{GameStage, GameState};
+use crate::stations::ActiveStation;
+
+pub struct StirringPlugin;
+
+impl Plugin for StirringPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.on_state_update(GameStage::Main, GameState::Cauldron, stir.system());
+    }
+}
+
+/// closer to the middle than this and the spoon isn't going round the pot
+const MIN_RADIUS: f32 = 15.0;
+/// further out than this and it's left the pot
+const MAX_RADIUS: f32 = 128.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StirDirection {
+    Clockwise,
+    Anticlockwise,
+}
+
+/// picks out circles drawn around a centre from a cursor path, a stir is
+/// counted every time the path goes all the way round
+#[derive(Debug, Default)]
+pub struct StirGesture {
+    /// the last point, relative to the centre
+    last: Option<Vec2>,
+    /// radians turned since the last stir, anticlockwise is positive
+    turned: f32,
+}
+
+impl StirGesture {
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.turned = 0.0;
+    }
+
+    /// the next point of the path, gives the direction of a stir if this point
+    /// completes one
+    pub fn feed(&mut self, centre: Vec2, point: Vec2) -> Option<StirDirection> {
+        let offset = point - centre;
+        let distance = offset.length();
+
+        if distance < MIN_RADIUS || distance > MAX_RADIUS {
+            self.reset();
+            return None;
+        }
+
+        if let Some(last) = self.last {
+            let cross = last.x * offset.y - last.y * offset.x;
+            self.turned += cross.atan2(last.dot(offset));
+        }
+        self.last = Some(offset);
+
+        if self.turned >= TAU {
+            self.turned -= TAU;
+            Some(StirDirection::Anticlockwise)
+        } else if self.turned <= -TAU {
+            self.turned += TAU;
+            Some(StirDirection::Clockwise)
+        } else {
+            None
+        }
+    }
+}
+
+/// holding the mouse down over the cauldron, without an ingredient, and going
+/// round stirs it
+fn stir(
+    mut gesture: Local<StirGesture>,
+    i_mouse_button: Res<Input<MouseButton>>,
+    active: Res<ActiveStation>,
+    mut q_station: Query<(&CauldronContents, &mut Brew)>,
+    q_cursor_state: Query<&CursorState>,
+    q_dragged: Query<(), With<Dragged>>,
+    q_cauldron: Query<&Transform, With<Cauldron>>,
+) {
+    let (contents, mut brew) = q_station.get_mut(active.0).unwrap();
+    let stirring = i_mouse_button.pressed(MouseButton::Left)
+        && q_dragged.iter().next().is_none()
+        && !contents.0.is_empty();
+
+    if !stirring {
+        gesture.reset();
+        return;
+    }
+
+    let cursor_state = q_cursor_state.iter().next().unwrap();
+    if !cursor_state.moved() {
+        return;
+    }
+
+    for transform in q_cauldron.iter() {
+        let centre = transform.translation.truncate();
+
+        if let Some(direction) = gesture.feed(centre, cursor_state.world()) {
+            brew.stir(direction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// points around a circle of `radius`, from `start` radians turning `turn`
+    /// radians in `steps` steps
+    fn arc(centre: Vec2, radius: f32, start: f32, turn: f32, steps: usize) -> Vec<Vec2> {
+        (0..=steps)
+            .map(|i| {
+                let angle = start + turn * i as f32 / steps as f32;
+                centre + Vec2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect()
+    }
+
+    fn stirs(gesture: &mut StirGesture, centre: Vec2, path: &[Vec2]) -> Vec<StirDirection> {
+        path.iter()
+            .filter_map(|point| gesture.feed(centre, *point))
+            .collect()
+    }
+
+    #[test]
+    fn clockwise_circle() {
+        let centre = Vec2::new(10.0, -20.0);
+        let path = arc(centre, 60.0, 0.0, -TAU * 1.1, 36);
+
+        let mut gesture = StirGesture::default();
+        assert_eq!(
+            stirs(&mut gesture, centre, &path),
+            vec![StirDirection::Clockwise]
+        );
+    }
+
+    #[test]
+    fn anticlockwise_circles() {
+        let centre = Vec2::zero();
+        let path = arc(centre, 100.0, 1.0, TAU * 2.1, 72);
+
+        let mut gesture = StirGesture::default();
+        assert_eq!(
+            stirs(&mut gesture, centre, &path),
+            vec![StirDirection::Anticlockwise, StirDirection::Anticlockwise]
+        );
+    }
+
+    #[test]
+    fn jittery_line_is_not_a_stir() {
+        let centre = Vec2::zero();
+        let across: Vec<Vec2> = (0..=40)
+            .map(|i| {
+                let jitter = if i % 2 == 0 { 6.0 } else { -6.0 };
+                Vec2::new(-100.0 + i as f32 * 5.0, 40.0 + jitter)
+            })
+            .collect();
+
+        // back and forth a few times
+        let mut path = vec![];
+        for _ in 0..3 {
+            path.extend(across.iter().cloned());
+            path.extend(across.iter().rev().cloned());
+        }
+
+        let mut gesture = StirGesture::default();
+        assert!(stirs(&mut gesture, centre, &path).is_empty());
+    }
+
+    #[test]
+    fn leaving_the_pot_starts_again() {
+        let centre = Vec2::zero();
+        let mut path = arc(centre, 60.0, 0.0, TAU * 0.75, 27);
+        path.push(Vec2::new(MAX_RADIUS + 50.0, 0.0));
+        path.extend(arc(centre, 60.0, TAU * 0.75, TAU * 0.75, 27));
+
+        let mut gesture = StirGesture::default();
+        assert!(stirs(&mut gesture, centre, &path).is_empty());
+
+        // a whole circle after coming back still counts
+        let path = arc(centre, 60.0, TAU * 0.5, TAU * 1.1, 36);
+        assert_eq!(
+            stirs(&mut gesture, centre, &path),
+            vec![StirDirection::Anticlockwise]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use bevy::{core::FixedTimestep, prelude::*};
+use rand::Rng;
+
+use crate::dragging::{Landed, TossedEvent};
+use crate::gamestate::{GameStage, GameState};
+use crate::viewport::{HEIGHT, WIDTH};
+
+pub struct TossPlugin;
+
+impl Plugin for TossPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // a fixed step so a toss goes the same way at any frame rate
+        app.add_stage_after(
+            CoreStage::Update,
+            TossStage,
+            SystemStage::parallel().with_run_criteria(FixedTimestep::step(STEP as f64)),
+        )
+        .add_system(start_toss.system())
+        .add_system_to_stage(TossStage, fly.system())
+        .add_system_to_stage(TossStage, fall_droplets.system())
+        // a splash doesn't follow the player into another scene
+        .on_state_exit(GameStage::Main, GameState::Main, teardown.system())
+        .on_state_exit(GameStage::Main, GameState::Cauldron, teardown.system());
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+struct TossStage;
+
+/// seconds between steps
+const STEP: f32 = 1.0 / 60.0;
+/// world units per second, per second
+const GRAVITY: f32 = -1500.0;
+/// how much of its speed a toss keeps bouncing up off a ledge
+const BOUNCE: f32 = 0.45;
+/// how much of its speed along a ledge a toss keeps when it bounces
+const FRICTION: f32 = 0.7;
+/// bouncing up slower than this, a toss stays on the ledge
+const REST_SPEED: f32 = 80.0;
+/// how far past the edge of the game a toss goes before it's given up on
+const OFF_SCREEN: f32 = 100.0;
+
+/// droplets in a splash
+const SPLASH_DROPLETS: usize = 12;
+/// seconds a droplet lasts
+const SPLASH_TIME: f32 = 0.6;
+const DROPLET_SIZE: f32 = 8.0;
+
+/// a flat surface tosses bounce off, as wide as `width` through its centre
+pub struct Ledge {
+    pub width: f32,
+}
+
+/// an opening tosses can fall into, `offset` from the centre of its entity.
+/// they land on the entity, splashing it with `splash`
+pub struct Mouth {
+    pub offset: Vec2,
+    pub width: f32,
+    pub splash: Color,
+}
+
+/// something thrown, moved each step until it lands
+struct Flying {
+    velocity: Vec2,
+}
+
+/// a drop of a splash
+struct Droplet {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// what became of a toss after a step
+#[derive(Debug, PartialEq)]
+enum Step {
+    Flying,
+    /// fell into the mouth at this index
+    Into(usize),
+    /// came to a rest, or went out of the game
+    Stopped,
+}
+
+/// whether a toss moving from `before` to `after` came down through a surface
+/// at `at`, `width` wide
+fn crossed(before: Vec2, after: Vec2, at: Vec2, width: f32) -> bool {
+    before.y >= at.y && after.y < at.y && (after.x - at.x).abs() <= width / 2.0
+}
+
+/// moves a toss on by one step. mouths and ledges are their centres and widths
+fn step(
+    position: &mut Vec2,
+    velocity: &mut Vec2,
+    mouths: &[(Vec2, f32)],
+    ledges: &[(Vec2, f32)],
+) -> Step {
+    let before = *position;
+    velocity.y += GRAVITY * STEP;
+    *position += *velocity * STEP;
+
+    if position.x.abs() > WIDTH / 2.0 + OFF_SCREEN || position.y < -HEIGHT / 2.0 - OFF_SCREEN {
+        return Step::Stopped;
+    }
+
+    // only things coming down land on anything
+    if velocity.y >= 0.0 {
+        return Step::Flying;
+    }
+
+    let after = *position;
+    if let Some(mouth) = mouths
+        .iter()
+        .position(|(at, width)| crossed(before, after, *at, *width))
+    {
+        return Step::Into(mouth);
+    }
+
+    if let Some((at, _)) = ledges
+        .iter()
+        .find(|(at, width)| crossed(before, after, *at, *width))
+    {
+        position.y = at.y;
+        velocity.y *= -BOUNCE;
+        velocity.x *= FRICTION;
+
+        if velocity.y < REST_SPEED {
+            return Step::Stopped;
+        }
+    }
+
+    Step::Flying
+}
+
+fn teardown(commands: &mut Commands, q_droplet: Query<Entity, With<Droplet>>) {
+    for entity in q_droplet.iter() {
+        commands.despawn(entity);
+    }
+}
+
+fn start_toss(commands: &mut Commands, mut event_reader: EventReader<TossedEvent>) {
+    for TossedEvent { entity, velocity } in event_reader.iter() {
+        commands.insert_one(
+            *entity,
+            Flying {
+                velocity: *velocity,
+            },
+        );
+    }
+}
+
+fn fly(
+    commands: &mut Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut q_flying: Query<(Entity, &mut Flying, &mut Transform)>,
+    q_mouth: Query<(Entity, &GlobalTransform, &Mouth)>,
+    q_ledge: Query<(&GlobalTransform, &Ledge)>,
+) {
+    let mouths: Vec<(Entity, Vec2, &Mouth)> = q_mouth
+        .iter()
+        .map(|(entity, global, mouth)| {
+            (entity, global.translation.truncate() + mouth.offset, mouth)
+        })
+        .collect();
+    let mouth_surfaces: Vec<(Vec2, f32)> = mouths
+        .iter()
+        .map(|(_, at, mouth)| (*at, mouth.width))
+        .collect();
+    let ledges: Vec<(Vec2, f32)> = q_ledge
+        .iter()
+        .map(|(global, ledge)| (global.translation.truncate(), ledge.width))
+        .collect();
+
+    for (entity, mut flying, mut transform) in q_flying.iter_mut() {
+        let mut position = transform.translation.truncate();
+        let outcome = step(
+            &mut position,
+            &mut flying.velocity,
+            &mouth_surfaces,
+            &ledges,
+        );
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+
+        let landed = match outcome {
+            Step::Flying => continue,
+            Step::Into(mouth) => {
+                let (target, at, mouth) = mouths[mouth];
+                splash(commands, &mut *materials, at, mouth.splash);
+                Some(target)
+            }
+            Step::Stopped => None,
+        };
+
+        commands.remove_one::<Flying>(entity);
+        commands.insert_one(entity, Landed(landed));
+    }
+}
+
+fn splash(commands: &mut Commands, materials: &mut Assets<ColorMaterial>, at: Vec2, colour: Color) {
+    let material = materials.add(colour.into());
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..SPLASH_DROPLETS {
+        let velocity = Vec2::new(rng.gen_range(-150.0..150.0), rng.gen_range(200.0..450.0));
+
+        commands
+            .spawn(SpriteBundle {
+                material: material.clone(),
+                sprite: Sprite::new(Vec2::new(DROPLET_SIZE, DROPLET_SIZE)),
+                transform: Transform::from_translation(at.extend(10.0)),
+                ..Default::default()
+            })
+            .with(Droplet {
+                velocity,
+                timer: Timer::from_seconds(SPLASH_TIME, false),
+            });
+    }
+}
+
+/// droplets arc out of the splash, shrinking until they're gone
+fn fall_droplets(
+    commands: &mut Commands,
+    mut q_droplet: Query<(Entity, &mut Droplet, &mut Transform)>,
+) {
+    for (entity, mut droplet, mut transform) in q_droplet.iter_mut() {
+        droplet.timer.tick(STEP);
+        if droplet.timer.finished() {
+            commands.despawn(entity);
+            continue;
+        }
+
+        droplet.velocity.y += GRAVITY * STEP;
+        transform.translation += (droplet.velocity * STEP).extend(0.0);
+        transform.scale = Vec3::splat(1.0 - droplet.timer.percent());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// steps a toss until it stops flying, with how many steps it took
+    fn fly_until_done(
+        position: &mut Vec2,
+        velocity: &mut Vec2,
+        mouths: &[(Vec2, f32)],
+        ledges: &[(Vec2, f32)],
+    ) -> (Step, usize) {
+        for steps in 1..10_000 {
+            match step(position, velocity, mouths, ledges) {
+                Step::Flying => {}
+                outcome => return (outcome, steps),
+            }
+        }
+        panic!("still flying at {:?}", position);
+    }
+
+    #[test]
+    fn arcs_into_a_mouth() {
+        // up and over, coming down through the second mouth
+        let mouths = [
+            (Vec2::new(-300.0, -100.0), 100.0),
+            (Vec2::new(90.0, -100.0), 100.0),
+        ];
+        let mut position = Vec2::zero();
+        let mut velocity = Vec2::new(100.0, 600.0);
+
+        let (outcome, steps) = fly_until_done(&mut position, &mut velocity, &mouths, &[]);
+        assert_eq!(outcome, Step::Into(1));
+        assert!(position.y < -100.0 && position.y > -130.0);
+
+        // the same toss always takes the same path
+        let mut again = (Vec2::zero(), Vec2::new(100.0, 600.0));
+        let (outcome, steps_again) = fly_until_done(&mut again.0, &mut again.1, &mouths, &[]);
+        assert_eq!(outcome, Step::Into(1));
+        assert_eq!(steps, steps_again);
+        assert_eq!(position, again.0);
+    }
+
+    #[test]
+    fn goes_up_through_a_mouth() {
+        // only things coming down fall in
+        let mouths = [(Vec2::new(0.0, 50.0), 100.0)];
+        let ledges = [(Vec2::new(0.0, -200.0), 400.0)];
+        let mut position = Vec2::zero();
+        let mut velocity = Vec2::new(0.0, 600.0);
+
+        for _ in 0..10 {
+            assert_eq!(
+                step(&mut position, &mut velocity, &mouths, &ledges),
+                Step::Flying
+            );
+        }
+        assert!(position.y > 50.0);
+    }
+
+    #[test]
+    fn bounces_to_a_rest_on_a_ledge() {
+        let ledges = [(Vec2::zero(), 400.0)];
+        let mut position = Vec2::new(0.0, 100.0);
+        let mut velocity = Vec2::new(50.0, 0.0);
+
+        let mut bounces = 0;
+        let outcome = loop {
+            let falling = velocity.y < 0.0;
+            let outcome = step(&mut position, &mut velocity, &[], &ledges);
+            if falling && velocity.y > 0.0 {
+                bounces += 1;
+            }
+            if outcome != Step::Flying {
+                break outcome;
+            }
+        };
+
+        assert_eq!(outcome, Step::Stopped);
+        assert!(bounces >= 2, "only bounced {} times", bounces);
+        assert_eq!(position.y, 0.0);
+        // each bounce slows it along the ledge too
+        assert!(velocity.x < 50.0 * FRICTION * FRICTION);
+    }
+
+    #[test]
+    fn leaves_the_screen() {
+        let mut position = Vec2::new(WIDTH / 2.0, 0.0);
+        let mut velocity = Vec2::new(800.0, 0.0);
+        let (outcome, _) = fly_until_done(&mut position, &mut velocity, &[], &[]);
+        assert_eq!(outcome, Step::Stopped);
+        assert!(position.x > WIDTH / 2.0 + OFF_SCREEN);
+
+        // and falling past the bottom, missing a ledge off to the side
+        let ledges = [(Vec2::new(300.0, -100.0), 50.0)];
+        let mut position = Vec2::zero();
+        let mut velocity = Vec2::zero();
+        let (outcome, _) = fly_until_done(&mut position, &mut velocity, &[], &ledges);
+        assert_eq!(outcome, Step::Stopped);
+        assert!(position.y < -HEIGHT / 2.0 - OFF_SCREEN);
+    }
+}
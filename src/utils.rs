@@ -1,24 +1,140 @@
-use bevy::prelude::*;
-
-pub fn average_colours<I: Iterator<Item = Color>>(it: I) -> Color {
-    let mut total = 0;
-    let (r, g, b) = it.fold((0.0, 0.0, 0.0), |(r, g, b), colour| {
-        total += 1;
-
-        (
-            r + colour.r().powi(2),
-            g + colour.g().powi(2),
-            b + colour.b().powi(2),
-        )
-    });
-
-    if total > 0 {
-        Color::rgb(
-            (r / total as f32).sqrt(),
-            (g / total as f32).sqrt(),
-            (b / total as f32).sqrt(),
-        )
+use bevy::{asset::FileAssetIo, prelude::*};
+use serde::de::DeserializeOwned;
+
+/// mixes colours the way paint looks to the eye, each weighted by how much of
+/// it there is. the average is taken in OKLab, so equal red and green make
+/// mustard (0.82, 0.66, 0.0) rather than a dull brown and black and white make
+/// a grey of 0.39. `None` when there's nothing to mix
+pub fn mix_colours<I: Iterator<Item = (Color, f32)>>(it: I) -> Option<Color> {
+    let mut total = 0.0;
+    let mut mixed = [0.0; 3];
+
+    for (colour, weight) in it {
+        let lab = to_oklab(colour);
+        for (m, c) in mixed.iter_mut().zip(&lab) {
+            *m += c * weight;
+        }
+        total += weight;
+    }
+
+    if total <= 0.0 {
+        return None;
+    }
+
+    Some(from_oklab([
+        mixed[0] / total,
+        mixed[1] / total,
+        mixed[2] / total,
+    ]))
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.max(0.0).min(1.0);
+
+    if c <= 0.0031308 {
+        c * 12.92
     } else {
-        Color::rgba(0.0, 0.0, 0.0, 0.0)
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// from https://bottosson.github.io/posts/oklab/
+#[allow(clippy::excessive_precision)]
+fn to_oklab(colour: Color) -> [f32; 3] {
+    let r = srgb_to_linear(colour.r());
+    let g = srgb_to_linear(colour.g());
+    let b = srgb_to_linear(colour.b());
+
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+#[allow(clippy::excessive_precision)]
+pub fn from_oklab([lightness, a, b]: [f32; 3]) -> Color {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    Color::rgb(
+        linear_to_srgb(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+        linear_to_srgb(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        linear_to_srgb(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+    )
+}
+
+/// load a ron file from `assets/data`, these are read once at startup so a
+/// broken file is a bug and we just bail out. the assets are found the same
+/// way the asset server finds them, not from the working directory
+pub fn load_data<T: DeserializeOwned>(name: &str) -> T {
+    let path = FileAssetIo::get_root_path().join("assets/data").join(name);
+
+    let contents = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("couldn't read {}: {}", path.display(), e));
+
+    ron::de::from_str(&contents)
+        .unwrap_or_else(|e| panic!("couldn't parse {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(colour: Color, [r, g, b]: [f32; 3]) {
+        let close = |a: f32, b: f32| (a - b).abs() < 0.005;
+        assert!(
+            close(colour.r(), r) && close(colour.g(), g) && close(colour.b(), b),
+            "{:?} isn't close to {:?}",
+            colour,
+            [r, g, b]
+        );
+    }
+
+    #[test]
+    fn red_and_green_make_mustard() {
+        let mixed = mix_colours(vec![(Color::RED, 1.0), (Color::GREEN, 1.0)].into_iter());
+        assert_close(mixed.unwrap(), [0.816, 0.660, 0.002]);
+    }
+
+    #[test]
+    fn black_and_white_make_grey() {
+        let mixed = mix_colours(vec![(Color::BLACK, 1.0), (Color::WHITE, 1.0)].into_iter());
+        assert_close(mixed.unwrap(), [0.389, 0.389, 0.389]);
+    }
+
+    #[test]
+    fn nothing_to_mix() {
+        assert!(mix_colours(std::iter::empty()).is_none());
+        assert!(mix_colours(vec![(Color::RED, 0.0)].into_iter()).is_none());
+    }
+
+    #[test]
+    fn weighted() {
+        let mixed = mix_colours(vec![(Color::RED, 3.0), (Color::GREEN, 1.0)].into_iter());
+        assert_close(mixed.unwrap(), [0.929, 0.453, 0.001]);
+
+        // only how much there is of each compared to the rest matters
+        let doubled = mix_colours(vec![(Color::RED, 2.0), (Color::GREEN, 2.0)].into_iter());
+        assert_close(doubled.unwrap(), [0.816, 0.660, 0.002]);
+    }
+
+    #[test]
+    fn one_colour_stays_the_same() {
+        let mixed = mix_colours(vec![(Color::rgb(0.2, 0.4, 0.6), 5.0)].into_iter());
+        assert_close(mixed.unwrap(), [0.2, 0.4, 0.6]);
     }
 }
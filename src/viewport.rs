@@ -0,0 +1,242 @@
+use bevy::{
+    prelude::*,
+    window::{WindowMode, WindowResized},
+};
+
+pub struct ViewportPlugin;
+
+impl Plugin for ViewportPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // after everything has made its ui but before it's laid out
+        app.add_stage_before(
+            CoreStage::PostUpdate,
+            ViewportStage,
+            SystemStage::parallel(),
+        )
+        .insert_resource(Viewport::default())
+        .add_startup_system(spawn_letterbox.system())
+        .add_system_to_stage(CoreStage::PreUpdate, fit_viewport.system())
+        .add_system(toggle_fullscreen.system())
+        .add_system(fit_camera.system())
+        .add_system_to_stage(ViewportStage, scale_new_styles.system())
+        .add_system_to_stage(ViewportStage, rescale_styles.system())
+        .add_system_to_stage(ViewportStage, scale_new_text.system())
+        .add_system_to_stage(ViewportStage, rescale_text.system());
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+struct ViewportStage;
+
+/// the size everything is laid out for, in world units and ui pixels
+pub const WIDTH: f32 = 800.0;
+pub const HEIGHT: f32 = 600.0;
+
+/// how far the letterbox bars reach past the edge of the screen
+const BAR_SIZE: f32 = 4000.0;
+
+/// the camera that looks at the game world, rather than the ui
+pub struct WorldCamera;
+
+/// how the `WIDTH` x `HEIGHT` game fits into the window, scaled up as far as it
+/// goes with bars filling the rest
+pub struct Viewport {
+    pub scale: f32,
+    /// the size of the bars on the left and right, and top and bottom
+    pub offset: Vec2,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport {
+            scale: 1.0,
+            offset: Vec2::zero(),
+        }
+    }
+}
+
+impl Viewport {
+    fn fit(width: f32, height: f32) -> Self {
+        let scale = (width / WIDTH).min(height / HEIGHT);
+
+        Viewport {
+            scale,
+            offset: Vec2::new(width - WIDTH * scale, height - HEIGHT * scale) / 2.0,
+        }
+    }
+}
+
+/// the style a ui node was made with, for `WIDTH` x `HEIGHT`
+struct BaseStyle(Style);
+
+/// the font sizes a text was made with, for `WIDTH` x `HEIGHT`
+struct BaseFontSizes(Vec<f32>);
+
+/// text whose sections are replaced by whatever owns it, it sets them to sizes
+/// already scaled by `Viewport::scale` so they're left alone here
+pub struct ScalesOwnText;
+
+fn fit_viewport(
+    windows: Res<Windows>,
+    mut viewport: ResMut<Viewport>,
+    mut fitted: Local<bool>,
+    mut event_reader: EventReader<WindowResized>,
+) {
+    let resized = event_reader.iter().last().is_some();
+    if *fitted && !resized {
+        return;
+    }
+    *fitted = true;
+
+    // sizes are logical pixels, so this holds for any dpi
+    let window = windows.get_primary().unwrap();
+    *viewport = Viewport::fit(window.width(), window.height());
+}
+
+/// F11 swaps between fullscreen and a window
+fn toggle_fullscreen(keys: Res<Input<KeyCode>>, mut windows: ResMut<Windows>) {
+    if !keys.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    let window = windows.get_primary_mut().unwrap();
+    let mode = match window.mode() {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+    window.set_mode(mode);
+}
+
+/// black bars around the edge of the game, only seen when the window is a
+/// different shape to it
+fn spawn_letterbox(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let material = materials.add(Color::BLACK.into());
+    let (across, up) = ((BAR_SIZE + WIDTH) / 2.0, (BAR_SIZE + HEIGHT) / 2.0);
+
+    for (x, y) in &[(-across, 0.0), (across, 0.0), (0.0, -up), (0.0, up)] {
+        commands.spawn(SpriteBundle {
+            material: material.clone(),
+            sprite: Sprite::new(Vec2::new(BAR_SIZE, BAR_SIZE)),
+            transform: Transform::from_xyz(*x, *y, 900.0),
+            ..Default::default()
+        });
+    }
+}
+
+/// zooms the world camera so the game fills the viewport
+fn fit_camera(viewport: Res<Viewport>, mut q_camera: Query<&mut Transform, With<WorldCamera>>) {
+    for mut transform in q_camera.iter_mut() {
+        let scale = Vec3::new(1.0 / viewport.scale, 1.0 / viewport.scale, 1.0);
+
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+fn scale_val(val: Val, scale: f32, offset: f32) -> Val {
+    match val {
+        Val::Px(px) => Val::Px(px * scale + offset),
+        val => val,
+    }
+}
+
+fn scale_size(size: Size<Val>, scale: f32) -> Size<Val> {
+    Size::new(
+        scale_val(size.width, scale, 0.0),
+        scale_val(size.height, scale, 0.0),
+    )
+}
+
+fn scale_rect(rect: Rect<Val>, scale: f32, offset: Vec2) -> Rect<Val> {
+    Rect {
+        left: scale_val(rect.left, scale, offset.x),
+        right: scale_val(rect.right, scale, offset.x),
+        top: scale_val(rect.top, scale, offset.y),
+        bottom: scale_val(rect.bottom, scale, offset.y),
+    }
+}
+
+/// pixel sizes in the ui scaled to the viewport, nodes at the top of the ui are
+/// moved in past the letterbox bars as well
+fn scale_style(base: &Style, viewport: &Viewport, root: bool) -> Style {
+    let scale = viewport.scale;
+    let offset = if root { viewport.offset } else { Vec2::zero() };
+
+    Style {
+        position: scale_rect(base.position, scale, offset),
+        margin: scale_rect(base.margin, scale, Vec2::zero()),
+        padding: scale_rect(base.padding, scale, Vec2::zero()),
+        border: scale_rect(base.border, scale, Vec2::zero()),
+        size: scale_size(base.size, scale),
+        min_size: scale_size(base.min_size, scale),
+        max_size: scale_size(base.max_size, scale),
+        flex_basis: scale_val(base.flex_basis, scale, 0.0),
+        ..base.clone()
+    }
+}
+
+/// ui is made for `WIDTH` x `HEIGHT`, this keeps what it was made with and
+/// scales it to the window
+fn scale_new_styles(
+    commands: &mut Commands,
+    viewport: Res<Viewport>,
+    mut q_style: Query<(Entity, &mut Style, Option<&Parent>), Added<Style>>,
+) {
+    for (entity, mut style, parent) in q_style.iter_mut() {
+        let base = style.clone();
+
+        *style = scale_style(&base, &*viewport, parent.is_none());
+        commands.insert_one(entity, BaseStyle(base));
+    }
+}
+
+fn rescale_styles(
+    viewport: Res<Viewport>,
+    mut scaled_to: Local<Option<(f32, Vec2)>>,
+    mut q_style: Query<(&BaseStyle, &mut Style, Option<&Parent>)>,
+) {
+    let fit = Some((viewport.scale, viewport.offset));
+    if *scaled_to == fit {
+        return;
+    }
+    *scaled_to = fit;
+
+    for (BaseStyle(base), mut style, parent) in q_style.iter_mut() {
+        *style = scale_style(base, &*viewport, parent.is_none());
+    }
+}
+
+/// font sizes are made for `WIDTH` x `HEIGHT` too, this keeps what they were
+/// made with and scales them to the window
+fn scale_new_text(
+    commands: &mut Commands,
+    viewport: Res<Viewport>,
+    mut q_text: Query<(Entity, &mut Text), (Added<Text>, Without<ScalesOwnText>)>,
+) {
+    for (entity, mut text) in q_text.iter_mut() {
+        let base: Vec<f32> = text.sections.iter().map(|s| s.style.font_size).collect();
+
+        for section in text.sections.iter_mut() {
+            section.style.font_size *= viewport.scale;
+        }
+        commands.insert_one(entity, BaseFontSizes(base));
+    }
+}
+
+fn rescale_text(
+    viewport: Res<Viewport>,
+    mut scaled_to: Local<Option<f32>>,
+    mut q_text: Query<(&BaseFontSizes, &mut Text)>,
+) {
+    if *scaled_to == Some(viewport.scale) {
+        return;
+    }
+    *scaled_to = Some(viewport.scale);
+
+    for (BaseFontSizes(base), mut text) in q_text.iter_mut() {
+        for (section, base) in text.sections.iter_mut().zip(base) {
+            section.style.font_size = base * viewport.scale;
+        }
+    }
+}
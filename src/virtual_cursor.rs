@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::dragging::{
+    CursorState, Draggable, Dragged, DropTarget, GrabEvent, Hoverable, PointAtEvent,
+};
+use crate::viewport::{HEIGHT, WIDTH};
+
+pub struct VirtualCursorPlugin;
+
+impl Plugin for VirtualCursorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(VirtualCursor::default())
+            .insert_resource(Gamepads::default())
+            .add_startup_system(setup.system())
+            .add_system(track_gamepads.system())
+            .add_system(hand_back_to_mouse.system())
+            .add_system(move_cursor.system())
+            .add_system(grab.system())
+            .add_system(show_cursor.system());
+    }
+}
+
+/// world units a full push of the stick moves the cursor each second
+const CURSOR_SPEED: f32 = 500.0;
+/// stick pushes smaller than this are ignored
+const DEAD_ZONE: f32 = 0.2;
+/// how close the cursor has to be when the stick is let go to snap onto
+/// something
+const SNAP_DISTANCE: f32 = 60.0;
+/// how much sideways distance counts against a snap, compared to distance in
+/// the direction pressed
+const SNAP_SIDEWAYS: f32 = 2.0;
+const CURSOR_SIZE: f32 = 12.0;
+
+/// a cursor for the gamepad and keyboard, moved by the stick or snapped
+/// between things with the arrow keys and d-pad
+#[derive(Default)]
+struct VirtualCursor {
+    position: Vec2,
+    /// in use since the mouse last moved
+    active: bool,
+    /// the stick was pushed last frame
+    sticking: bool,
+}
+
+/// the gamepads plugged in
+#[derive(Default)]
+struct Gamepads(Vec<Gamepad>);
+
+/// the sprite drawn at the virtual cursor
+struct VirtualCursorSprite;
+
+fn setup(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    commands
+        .spawn(SpriteBundle {
+            material: materials.add(Color::rgba(1.0, 1.0, 1.0, 0.8).into()),
+            sprite: Sprite::new(Vec2::new(CURSOR_SIZE, CURSOR_SIZE)),
+            transform: Transform::from_xyz(0.0, 0.0, 950.0),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .with(VirtualCursorSprite);
+}
+
+fn track_gamepads(
+    mut gamepads: Local<HashSet<Gamepad>>,
+    mut connected: ResMut<Gamepads>,
+    mut event_reader: EventReader<GamepadEvent>,
+) {
+    let mut changed = false;
+
+    for GamepadEvent(gamepad, event) in event_reader.iter() {
+        match event {
+            GamepadEventType::Connected => changed |= gamepads.insert(*gamepad),
+            GamepadEventType::Disconnected => changed |= gamepads.remove(gamepad),
+            _ => {}
+        }
+    }
+
+    if changed {
+        connected.0 = gamepads.iter().copied().collect();
+    }
+}
+
+/// moving the mouse puts it back in charge, the virtual cursor picks up from
+/// wherever it left off
+fn hand_back_to_mouse(
+    mut cursor: ResMut<VirtualCursor>,
+    q_cursor_state: Query<&CursorState>,
+    mut event_reader: EventReader<CursorMoved>,
+) {
+    if event_reader.iter().last().is_none() {
+        return;
+    }
+
+    cursor.active = false;
+    if let Some(cursor_state) = q_cursor_state.iter().next() {
+        cursor.position = cursor_state.world();
+    }
+}
+
+/// the way the arrow keys or d-pad were pressed this frame
+fn snap_direction(
+    keys: &Input<KeyCode>,
+    buttons: &Input<GamepadButton>,
+    gamepads: &[Gamepad],
+) -> Option<Vec2> {
+    let pressed = |key: KeyCode, button: GamepadButtonType| {
+        keys.just_pressed(key)
+            || gamepads
+                .iter()
+                .any(|gamepad| buttons.just_pressed(GamepadButton(*gamepad, button)))
+    };
+
+    if pressed(KeyCode::Left, GamepadButtonType::DPadLeft) {
+        Some(Vec2::new(-1.0, 0.0))
+    } else if pressed(KeyCode::Right, GamepadButtonType::DPadRight) {
+        Some(Vec2::new(1.0, 0.0))
+    } else if pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+        Some(Vec2::new(0.0, 1.0))
+    } else if pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+        Some(Vec2::new(0.0, -1.0))
+    } else {
+        None
+    }
+}
+
+/// the stick furthest from the middle, if any are pushed past the dead zone
+fn stick(axes: &Axis<GamepadAxis>, gamepads: &[Gamepad]) -> Option<Vec2> {
+    gamepads
+        .iter()
+        .map(|gamepad| {
+            let axis = |axis| axes.get(GamepadAxis(*gamepad, axis)).unwrap_or_default();
+            Vec2::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            )
+        })
+        .filter(|push| push.length() > DEAD_ZONE)
+        .max_by(|a, b| a.length().partial_cmp(&b.length()).unwrap())
+}
+
+/// the nearest of `targets` from `from` in `direction`, within 45 degrees
+fn snap_towards(from: Vec2, direction: Vec2, targets: &[Vec2]) -> Option<Vec2> {
+    targets
+        .iter()
+        .filter_map(|target| {
+            let offset = *target - from;
+            let along = offset.dot(direction);
+            let sideways = (offset - direction * along).length();
+
+            if along <= 0.0 || sideways > along {
+                return None;
+            }
+            Some((*target, along + sideways * SNAP_SIDEWAYS))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(target, _)| target)
+}
+
+/// the nearest of `targets` to `from`, if any are close enough to snap to
+fn snap_near(from: Vec2, targets: &[Vec2]) -> Option<Vec2> {
+    targets
+        .iter()
+        .map(|target| (*target, (*target - from).length()))
+        .filter(|(_, distance)| *distance <= SNAP_DISTANCE)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(target, _)| target)
+}
+
+fn move_cursor(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut cursor: ResMut<VirtualCursor>,
+    mut ev_point_at: ResMut<Events<PointAtEvent>>,
+    q_dragged: Query<&Draggable, With<Dragged>>,
+    q_interactable: Query<
+        (&GlobalTransform, Option<&Hoverable>, Option<&DropTarget>),
+        (Or<(With<Hoverable>, With<DropTarget>)>, Without<Dragged>),
+    >,
+) {
+    // while dragging, only the things it could be dropped on are snapped to
+    let dragged = q_dragged.iter().next().map(|Draggable(kind)| *kind);
+    let targets: Vec<Vec2> = q_interactable
+        .iter()
+        .filter(|(_, hoverable, target)| match dragged {
+            Some(kind) => target.map_or(false, |t| t.will_accept(kind)),
+            None => hoverable.is_some(),
+        })
+        .map(|(global, _, _)| global.translation.truncate())
+        .collect();
+
+    let push = stick(&*axes, &gamepads.0);
+    let was_sticking = cursor.sticking;
+    cursor.sticking = push.is_some();
+
+    let (position, jump) = if let Some(push) = push {
+        (
+            cursor.position + push * CURSOR_SPEED * time.delta_seconds(),
+            false,
+        )
+    } else if let Some(direction) = snap_direction(&*keys, &*buttons, &gamepads.0) {
+        match snap_towards(cursor.position, direction, &targets) {
+            Some(target) => (target, true),
+            None => return,
+        }
+    } else if was_sticking {
+        match snap_near(cursor.position, &targets) {
+            Some(target) => (target, true),
+            None => return,
+        }
+    } else {
+        return;
+    };
+
+    cursor.active = true;
+    cursor.position = Vec2::new(
+        position.x.max(-WIDTH / 2.0).min(WIDTH / 2.0),
+        position.y.max(-HEIGHT / 2.0).min(HEIGHT / 2.0),
+    );
+    ev_point_at.send(PointAtEvent {
+        world: cursor.position,
+        jump,
+    });
+}
+
+/// z or the bottom face button picks up what's under the virtual cursor, and
+/// lets go of it the next time. space and enter are left for the dialogue
+fn grab(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut cursor: ResMut<VirtualCursor>,
+    mut ev_grab: ResMut<Events<GrabEvent>>,
+    q_dragged: Query<(), With<Dragged>>,
+) {
+    let pressed = keys.just_pressed(KeyCode::Z)
+        || gamepads
+            .0
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton(*gamepad, GamepadButtonType::South)));
+    if !pressed {
+        return;
+    }
+
+    cursor.active = true;
+    if q_dragged.iter().next().is_some() {
+        ev_grab.send(GrabEvent::Released);
+    } else {
+        ev_grab.send(GrabEvent::Pressed);
+    }
+}
+
+fn show_cursor(
+    cursor: Res<VirtualCursor>,
+    mut q_sprite: Query<(&mut Transform, &mut Visible), With<VirtualCursorSprite>>,
+) {
+    for (mut transform, mut visible) in q_sprite.iter_mut() {
+        if visible.is_visible != cursor.active {
+            visible.is_visible = cursor.active;
+        }
+
+        transform.translation.x = cursor.position.x;
+        transform.translation.y = cursor.position.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, core::CorePlugin};
+
+    use super::*;
+    use crate::alpha_mask::AlphaMaskPlugin;
+    use crate::dragging::{DragKind, DragPlugin};
+
+    fn push(axes: &mut Axis<GamepadAxis>, gamepad: Gamepad, x: f32, y: f32) {
+        axes.set(GamepadAxis(gamepad, GamepadAxisType::LeftStickX), x);
+        axes.set(GamepadAxis(gamepad, GamepadAxisType::LeftStickY), y);
+    }
+
+    #[test]
+    fn stick_takes_the_furthest_push() {
+        let (first, second, unplugged) = (Gamepad(0), Gamepad(1), Gamepad(2));
+        let mut axes = Axis::<GamepadAxis>::default();
+
+        push(&mut axes, first, 0.1, 0.1);
+        push(&mut axes, unplugged, 1.0, 0.0);
+        assert_eq!(stick(&axes, &[first, second]), None);
+
+        push(&mut axes, second, 0.5, 0.0);
+        push(&mut axes, first, 0.1, -0.9);
+        assert_eq!(stick(&axes, &[first, second]), Some(Vec2::new(0.1, -0.9)));
+    }
+
+    #[test]
+    fn snaps_towards_the_nearest_in_that_direction() {
+        let targets = [
+            Vec2::new(100.0, 0.0),
+            Vec2::new(50.0, 40.0),
+            Vec2::new(40.0, 60.0),
+            Vec2::new(-30.0, 0.0),
+        ];
+        let from = Vec2::zero();
+
+        // straight ahead beats closer but off to the side
+        assert_eq!(
+            snap_towards(from, Vec2::new(1.0, 0.0), &targets),
+            Some(Vec2::new(100.0, 0.0))
+        );
+        assert_eq!(
+            snap_towards(from, Vec2::new(-1.0, 0.0), &targets),
+            Some(Vec2::new(-30.0, 0.0))
+        );
+        // (50, 40) is more than 45 degrees off up
+        assert_eq!(
+            snap_towards(from, Vec2::new(0.0, 1.0), &targets),
+            Some(Vec2::new(40.0, 60.0))
+        );
+        assert_eq!(snap_towards(from, Vec2::new(0.0, -1.0), &targets), None);
+    }
+
+    #[test]
+    fn snaps_near_only_when_close() {
+        let targets = [Vec2::new(50.0, 0.0), Vec2::new(30.0, 30.0)];
+
+        assert_eq!(
+            snap_near(Vec2::zero(), &targets),
+            Some(Vec2::new(30.0, 30.0))
+        );
+        assert_eq!(snap_near(Vec2::new(-100.0, 0.0), &targets), None);
+    }
+
+    fn press(app: &mut App, button: GamepadButton) {
+        let mut buttons = app.resources.get_mut::<Input<GamepadButton>>().unwrap();
+        // what the input plugin does at the start of every frame
+        buttons.update();
+        buttons.press(button);
+    }
+
+    fn let_go(app: &mut App) {
+        let mut buttons = app.resources.get_mut::<Input<GamepadButton>>().unwrap();
+        let held: Vec<GamepadButton> = buttons.get_pressed().copied().collect();
+        for button in held {
+            buttons.release(button);
+        }
+    }
+
+    /// a frame, then another for anything reading events sent in it
+    fn frames(app: &mut App) {
+        app.update();
+        let_go(app);
+        app.update();
+    }
+
+    #[test]
+    fn gamepad_snaps_and_grabs() {
+        let mut builder = App::build();
+        builder
+            .add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Texture>()
+            .add_asset::<ColorMaterial>()
+            .add_asset::<TextureAtlas>()
+            .insert_resource(Windows::default())
+            .insert_resource(Input::<KeyCode>::default())
+            .insert_resource(Input::<MouseButton>::default())
+            .insert_resource(Input::<GamepadButton>::default())
+            .insert_resource(Axis::<GamepadAxis>::default())
+            .add_event::<CursorMoved>()
+            .add_event::<GamepadEvent>()
+            .add_plugin(AlphaMaskPlugin)
+            .add_plugin(DragPlugin)
+            .add_plugin(VirtualCursorPlugin);
+        let mut app = builder.app;
+
+        let at = Vec3::new(100.0, 0.0, 1.0);
+        let jar = app.world.spawn((
+            Transform::from_translation(at),
+            GlobalTransform::from_translation(at),
+            Sprite::new(Vec2::new(40.0, 40.0)),
+            Hoverable,
+            Draggable(DragKind::Jar),
+        ));
+
+        let gamepad = Gamepad(0);
+        app.resources
+            .get_mut::<Events<GamepadEvent>>()
+            .unwrap()
+            .send(GamepadEvent(gamepad, GamepadEventType::Connected));
+        app.update();
+
+        // the d-pad jumps the cursor onto the jar
+        press(
+            &mut app,
+            GamepadButton(gamepad, GamepadButtonType::DPadRight),
+        );
+        frames(&mut app);
+
+        let world = app.world.query::<&CursorState>().next().unwrap().world();
+        assert_eq!(world, Vec2::new(100.0, 0.0));
+        assert!(app.resources.get::<VirtualCursor>().unwrap().active);
+
+        // the bottom button picks it up, and lets go the next time
+        press(&mut app, GamepadButton(gamepad, GamepadButtonType::South));
+        frames(&mut app);
+        assert!(app.world.get::<Dragged>(jar).is_ok());
+
+        press(&mut app, GamepadButton(gamepad, GamepadButtonType::South));
+        frames(&mut app);
+        assert!(app.world.get::<Dragged>(jar).is_err());
+    }
+}